@@ -0,0 +1,375 @@
+use std::{env, sync::Arc};
+
+use regex::Regex;
+use serde::{de::IntoDeserializer, Deserialize, Serialize, Serializer};
+
+use crate::{
+    cost_matrix::CostMatrix,
+    distance::{Algorithm, DEFAULT_DP_BUFFER_MAX_LEN},
+    join::JoinTable,
+    normalize::{Normalizer, DEFAULT_SUFFIX_PATTERN},
+    search::{DEFAULT_SCORE_PRECISION, MAX_SCORE_PRECISION},
+    transliterate::Transliterator,
+};
+
+// Serializzata così com'è da GET /config: i campi che non si possono mostrare
+// diventano un booleano "caricato", il token non compare mai
+#[derive(Serialize)]
+pub struct Config {
+    pub bind: String,
+    pub names_path: String,
+    // vuoto: file a una colonna, la riga intera è il nome
+    pub columns: Vec<String>,
+    pub delimiter: char,
+    pub name_column: Option<String>,
+    pub aliases_column: Option<String>,
+    pub dedup_tokens: bool,
+    // suffisso da ignorare nel matching, vedi `Normalizer`
+    #[serde(serialize_with = "serialize_pattern")]
+    pub strip_suffix: Option<Regex>,
+    pub empty_query: EmptyQuery,
+    pub frequency_column: Option<String>,
+    // tetto per il suggerimento `parallelism` delle singole richieste
+    pub max_parallelism: usize,
+    pub max_response_bytes: usize,
+    pub oversize_policy: OversizePolicy,
+    pub warm_up: bool,
+    pub mlock: bool,
+    pub parallel_load: bool,
+    #[serde(serialize_with = "serialize_loaded")]
+    pub cost_matrix: Option<Arc<CostMatrix>>,
+    #[serde(serialize_with = "serialize_loaded")]
+    pub transliterator: Option<Transliterator>,
+    // attributi per `enrich`, cercati con il campo `join_key` (il nome se assente)
+    #[serde(serialize_with = "serialize_loaded")]
+    pub join: Option<Arc<JoinTable>>,
+    pub join_key: Option<String>,
+    // righe DP più lunghe non restano nel buffer riusato da ogni thread
+    pub dp_buffer_max_len: usize,
+    pub timestamp_column: Option<String>,
+    pub missing_timestamp: MissingTimestamp,
+    // richieste più lente di così finiscono nel log con il loro id
+    pub slow_query_ms: u64,
+    // righe tra un log di avanzamento e l'altro, 0 per nessun log
+    pub progress_every: u64,
+    pub background_load: bool,
+    pub canary: Option<Canary>,
+    // 0: nessun controllo
+    pub min_distinct_chars: usize,
+    pub low_variety_policy: LowVarietyPolicy,
+    // query ricordate per /suggest, 0: nessuna cronologia
+    pub history_size: usize,
+    pub score_precision: u32,
+    // POST /names, spento di default
+    pub allow_append: bool,
+    // righe massime per richiesta di POST /names
+    pub max_append_lines: usize,
+    // se impostato, GET /config e POST /names lo richiedono come bearer token
+    #[serde(skip)]
+    pub admin_token: Option<String>,
+}
+
+fn serialize_pattern<S: Serializer>(
+    regex: &Option<Regex>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_some(&regex.as_ref().map(Regex::as_str))
+}
+
+fn serialize_loaded<T, S: Serializer>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bool(value.is_some())
+}
+
+// Esperimento A/B: `percent` delle query (per hash) usa `algorithm`
+#[derive(Serialize)]
+pub struct Canary {
+    pub algorithm: Algorithm,
+    pub percent: u8,
+}
+
+// Record senza timestamp (o con uno illeggibile) nei filtri `since`/`recency_days`
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingTimestamp {
+    Exclude,
+    Include,
+}
+
+// Cosa fare quando la risposta supererebbe `max_response_bytes`
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OversizePolicy {
+    Reject,
+    Truncate,
+}
+
+// Cosa fare con le query con meno di `min_distinct_chars` caratteri distinti
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LowVarietyPolicy {
+    Reject,
+    Warn,
+}
+
+// Cosa restituire per una query vuota
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmptyQuery {
+    None,
+    Popular,
+    Alphabetical,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Config, String> {
+        let columns = env::var("FUZZY_COLUMNS")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|column| column.trim().to_string())
+                    .filter(|column| !column.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let delimiter = match env::var("FUZZY_DELIMITER") {
+            Ok(value) => {
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(delimiter), None) => delimiter,
+                    _ => return Err("FUZZY_DELIMITER must be a single character".to_string()),
+                }
+            }
+            Err(_) => ',',
+        };
+
+        let empty_query = match env::var("FUZZY_EMPTY_QUERY").as_deref() {
+            Err(_) | Ok("none") => EmptyQuery::None,
+            Ok("popular") => EmptyQuery::Popular,
+            Ok("alphabetical") => EmptyQuery::Alphabetical,
+            Ok(other) => return Err(format!("unknown FUZZY_EMPTY_QUERY `{other}`")),
+        };
+
+        let max_parallelism = match env::var("FUZZY_MAX_PARALLELISM") {
+            Ok(value) => match value.parse::<usize>() {
+                Ok(threads) if threads > 0 => threads,
+                _ => return Err("FUZZY_MAX_PARALLELISM must be a positive integer".to_string()),
+            },
+            Err(_) => rayon::current_num_threads(),
+        };
+
+        let max_response_bytes = match env::var("FUZZY_MAX_RESPONSE_BYTES") {
+            Ok(value) => value
+                .parse::<usize>()
+                .map_err(|_| "FUZZY_MAX_RESPONSE_BYTES must be a byte count".to_string())?,
+            Err(_) => 1024 * 1024,
+        };
+
+        let oversize_policy = match env::var("FUZZY_OVERSIZE_POLICY").as_deref() {
+            Err(_) | Ok("reject") => OversizePolicy::Reject,
+            Ok("truncate") => OversizePolicy::Truncate,
+            Ok(other) => return Err(format!("unknown FUZZY_OVERSIZE_POLICY `{other}`")),
+        };
+
+        let min_distinct_chars = match env::var("FUZZY_MIN_DISTINCT_CHARS") {
+            Ok(value) => value.parse::<usize>().map_err(|_| {
+                "FUZZY_MIN_DISTINCT_CHARS must be a non-negative integer".to_string()
+            })?,
+            Err(_) => 0,
+        };
+
+        let low_variety_policy = match env::var("FUZZY_LOW_VARIETY_POLICY").as_deref() {
+            Err(_) | Ok("reject") => LowVarietyPolicy::Reject,
+            Ok("warn") => LowVarietyPolicy::Warn,
+            Ok(other) => return Err(format!("unknown FUZZY_LOW_VARIETY_POLICY `{other}`")),
+        };
+
+        let max_append_lines = match env::var("FUZZY_MAX_APPEND_LINES") {
+            Ok(value) => match value.parse::<usize>() {
+                Ok(lines) if lines > 0 => lines,
+                _ => return Err("FUZZY_MAX_APPEND_LINES must be a positive integer".to_string()),
+            },
+            Err(_) => 1000,
+        };
+
+        let history_size = match env::var("FUZZY_HISTORY_SIZE") {
+            Ok(value) => value
+                .parse::<usize>()
+                .map_err(|_| "FUZZY_HISTORY_SIZE must be a non-negative integer".to_string())?,
+            Err(_) => 1000,
+        };
+
+        let score_precision = match env::var("FUZZY_SCORE_PRECISION") {
+            Ok(value) => match value.parse::<u32>() {
+                Ok(digits) if digits <= MAX_SCORE_PRECISION => digits,
+                _ => {
+                    return Err(format!(
+                        "FUZZY_SCORE_PRECISION must be in 0..={MAX_SCORE_PRECISION}"
+                    ))
+                }
+            },
+            Err(_) => DEFAULT_SCORE_PRECISION,
+        };
+
+        let dp_buffer_max_len = match env::var("FUZZY_DP_BUFFER_MAX_LEN") {
+            Ok(value) => value
+                .parse::<usize>()
+                .map_err(|_| "FUZZY_DP_BUFFER_MAX_LEN must be a length in bytes".to_string())?,
+            Err(_) => DEFAULT_DP_BUFFER_MAX_LEN,
+        };
+
+        let missing_timestamp = match env::var("FUZZY_MISSING_TIMESTAMP").as_deref() {
+            Err(_) | Ok("exclude") => MissingTimestamp::Exclude,
+            Ok("include") => MissingTimestamp::Include,
+            Ok(other) => return Err(format!("unknown FUZZY_MISSING_TIMESTAMP `{other}`")),
+        };
+
+        let slow_query_ms = match env::var("FUZZY_SLOW_QUERY_MS") {
+            Ok(value) => value
+                .parse::<u64>()
+                .map_err(|_| "FUZZY_SLOW_QUERY_MS must be a number of milliseconds".to_string())?,
+            Err(_) => 1000,
+        };
+
+        let progress_every = match env::var("FUZZY_PROGRESS_EVERY") {
+            Ok(value) => value
+                .parse::<u64>()
+                .map_err(|_| "FUZZY_PROGRESS_EVERY must be a number of lines".to_string())?,
+            Err(_) => 0,
+        };
+
+        let canary = match env::var("FUZZY_CANARY_ALGORITHM") {
+            Ok(name) => {
+                let algorithm = Algorithm::deserialize(name.as_str().into_deserializer()).map_err(
+                    |_: serde::de::value::Error| format!("unknown FUZZY_CANARY_ALGORITHM `{name}`"),
+                )?;
+                let percent = match env::var("FUZZY_CANARY_PERCENT") {
+                    Ok(value) => match value.parse::<u8>() {
+                        Ok(percent) if percent <= 100 => percent,
+                        _ => return Err("FUZZY_CANARY_PERCENT must be in 0..=100".to_string()),
+                    },
+                    Err(_) => 0,
+                };
+                Some(Canary { algorithm, percent })
+            }
+            Err(_) => None,
+        };
+
+        let strip_suffix = match env::var("FUZZY_STRIP_SUFFIX_PATTERN") {
+            Ok(pattern) => Some(Normalizer::suffix_regex(&pattern)?),
+            Err(_) if env_flag("FUZZY_STRIP_SUFFIX") => {
+                Some(Normalizer::suffix_regex(DEFAULT_SUFFIX_PATTERN)?)
+            }
+            Err(_) => None,
+        };
+
+        let transliterator = match env::var("FUZZY_TRANSLITERATION_TABLE") {
+            Ok(path) => Some(Transliterator::load(&path)?),
+            Err(_) => env_flag("FUZZY_TRANSLITERATE").then(Transliterator::builtin),
+        };
+
+        let config = Config {
+            bind: env::var("FUZZY_BIND").unwrap_or_else(|_| "127.0.0.1:3000".to_string()),
+            names_path: env::var("FUZZY_NAMES_PATH").unwrap_or_else(|_| "./names.csv".to_string()),
+            columns,
+            delimiter,
+            name_column: env::var("FUZZY_NAME_COLUMN").ok(),
+            aliases_column: env::var("FUZZY_ALIASES_COLUMN").ok(),
+            dedup_tokens: env_flag("FUZZY_DEDUP_TOKENS"),
+            strip_suffix,
+            empty_query,
+            frequency_column: env::var("FUZZY_FREQUENCY_COLUMN").ok(),
+            max_parallelism,
+            max_response_bytes,
+            oversize_policy,
+            warm_up: env_flag("FUZZY_WARM_UP"),
+            mlock: env_flag("FUZZY_MLOCK"),
+            parallel_load: env_flag("FUZZY_PARALLEL_LOAD"),
+            cost_matrix: match env::var("FUZZY_COST_MATRIX") {
+                Ok(path) => Some(Arc::new(CostMatrix::load(&path)?)),
+                Err(_) => None,
+            },
+            transliterator,
+            join: match env::var("FUZZY_JOIN_PATH") {
+                Ok(path) => Some(Arc::new(JoinTable::load(&path)?)),
+                Err(_) => None,
+            },
+            join_key: env::var("FUZZY_JOIN_KEY").ok(),
+            dp_buffer_max_len,
+            timestamp_column: env::var("FUZZY_TIMESTAMP_COLUMN").ok(),
+            missing_timestamp,
+            slow_query_ms,
+            progress_every,
+            background_load: env_flag("FUZZY_BACKGROUND_LOAD"),
+            canary,
+            min_distinct_chars,
+            low_variety_policy,
+            history_size,
+            score_precision,
+            allow_append: env_flag("FUZZY_ALLOW_APPEND"),
+            max_append_lines,
+            admin_token: env::var("FUZZY_ADMIN_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty()),
+        };
+
+        if let Some(name_column) = &config.name_column {
+            if !config.columns.contains(name_column) {
+                return Err(format!(
+                    "FUZZY_NAME_COLUMN `{name_column}` is not in FUZZY_COLUMNS"
+                ));
+            }
+        }
+
+        if let Some(aliases_column) = &config.aliases_column {
+            if !config.columns.contains(aliases_column) {
+                return Err(format!(
+                    "FUZZY_ALIASES_COLUMN `{aliases_column}` is not in FUZZY_COLUMNS"
+                ));
+            }
+        }
+
+        if let Some(timestamp_column) = &config.timestamp_column {
+            if !config.columns.contains(timestamp_column) {
+                return Err(format!(
+                    "FUZZY_TIMESTAMP_COLUMN `{timestamp_column}` is not in FUZZY_COLUMNS"
+                ));
+            }
+        }
+
+        if let Some(join_key) = &config.join_key {
+            if !config.columns.contains(join_key) {
+                return Err(format!(
+                    "FUZZY_JOIN_KEY `{join_key}` is not in FUZZY_COLUMNS"
+                ));
+            }
+        }
+
+        if config
+            .canary
+            .as_ref()
+            .is_some_and(|canary| canary.algorithm == Algorithm::Weighted)
+            && config.cost_matrix.is_none()
+        {
+            return Err("FUZZY_CANARY_ALGORITHM=weighted needs FUZZY_COST_MATRIX".to_string());
+        }
+
+        match &config.frequency_column {
+            Some(column) if !config.columns.contains(column) => {
+                return Err(format!(
+                    "FUZZY_FREQUENCY_COLUMN `{column}` is not in FUZZY_COLUMNS"
+                ));
+            }
+            None if config.empty_query == EmptyQuery::Popular => {
+                return Err("FUZZY_EMPTY_QUERY=popular needs FUZZY_FREQUENCY_COLUMN".to_string());
+            }
+            _ => {}
+        }
+
+        Ok(config)
+    }
+}
+
+fn env_flag(name: &str) -> bool {
+    env::var(name).is_ok_and(|value| matches!(value.as_str(), "1" | "true" | "yes"))
+}
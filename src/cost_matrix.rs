@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::distance::count_dp_cells;
+
+// Formato del file JSON (FUZZY_COST_MATRIX)
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCostMatrix {
+    #[serde(default = "unit_cost")]
+    insertion: usize,
+    #[serde(default = "unit_cost")]
+    deletion: usize,
+    // costo delle sostituzioni non elencate
+    #[serde(default = "unit_cost")]
+    substitution: usize,
+    // chiave di due caratteri: "ae" è il costo per sostituire `a` con `e`
+    #[serde(default)]
+    substitutions: HashMap<String, usize>,
+}
+
+fn unit_cost() -> usize {
+    1
+}
+
+// Tetto per ogni costo: basta per qualunque pesatura e tiene il DP lontano
+// dall'overflow anche sui nomi più lunghi
+pub const MAX_EDIT_COST: usize = 1000;
+
+// Costi per l'algoritmo `weighted`, già validati
+pub struct CostMatrix {
+    insertion: usize,
+    deletion: usize,
+    substitution: usize,
+    substitutions: HashMap<(char, char), usize>,
+}
+
+impl CostMatrix {
+    pub fn load(path: &str) -> Result<CostMatrix, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| format!("cannot read FUZZY_COST_MATRIX `{path}`: {error}"))?;
+        let raw: RawCostMatrix = serde_json::from_str(&text)
+            .map_err(|error| format!("invalid FUZZY_COST_MATRIX `{path}`: {error}"))?;
+
+        // con un costo nullo ogni stringa sarebbe a distanza 0 da ogni altra
+        if raw.insertion == 0 || raw.deletion == 0 {
+            return Err("FUZZY_COST_MATRIX insertion and deletion costs must be at least 1".into());
+        }
+        if [raw.insertion, raw.deletion, raw.substitution]
+            .into_iter()
+            .chain(raw.substitutions.values().copied())
+            .any(|cost| cost > MAX_EDIT_COST)
+        {
+            return Err(format!(
+                "FUZZY_COST_MATRIX costs must be at most {MAX_EDIT_COST}"
+            ));
+        }
+
+        let mut pairs = Vec::with_capacity(raw.substitutions.len());
+        for (key, cost) in &raw.substitutions {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next(), chars.next()) {
+                (Some(from), Some(to), None) if from != to => pairs.push(((from, to), *cost)),
+                _ => {
+                    return Err(format!(
+                        "FUZZY_COST_MATRIX key `{key}` must be two different characters"
+                    ))
+                }
+            }
+        }
+
+        // le coppie valgono in entrambe le direzioni, salvo quelle elencate
+        // esplicitamente anche al contrario
+        let mut substitutions = HashMap::with_capacity(pairs.len() * 2);
+        for &((from, to), cost) in &pairs {
+            substitutions.insert((from, to), cost);
+        }
+        for &((from, to), cost) in &pairs {
+            substitutions.entry((to, from)).or_insert(cost);
+        }
+
+        Ok(CostMatrix {
+            insertion: raw.insertion,
+            deletion: raw.deletion,
+            substitution: raw.substitution,
+            substitutions,
+        })
+    }
+
+    fn substitution_cost(&self, from: char, to: char) -> usize {
+        if from == to {
+            0
+        } else {
+            *self
+                .substitutions
+                .get(&(from, to))
+                .unwrap_or(&self.substitution)
+        }
+    }
+
+    // Levenshtein pesato, per caratteri e non per byte: le chiavi della
+    // matrice sono caratteri Unicode
+    pub fn distance(&self, a: &str, b: &str) -> usize {
+        let b: Vec<char> = b.chars().collect();
+        count_dp_cells(a.chars().count(), b.len());
+
+        let mut prev: Vec<usize> = (0..=b.len()).map(|j| j * self.insertion).collect();
+        let mut curr = vec![0; b.len() + 1];
+
+        for (i, char_a) in a.chars().enumerate() {
+            curr[0] = (i + 1) * self.deletion;
+
+            for (j, &char_b) in b.iter().enumerate() {
+                curr[j + 1] = (prev[j + 1] + self.deletion)
+                    .min(curr[j] + self.insertion)
+                    .min(prev[j] + self.substitution_cost(char_a, char_b));
+            }
+
+            std::mem::swap(&mut prev, &mut curr);
+        }
+
+        prev[b.len()]
+    }
+}
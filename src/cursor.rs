@@ -0,0 +1,33 @@
+// Cursore opaco per la paginazione: la chiave di ordinamento dell'ultimo
+// risultato restituito (distanza, indice del record), più la versione del
+// dataset e l'hash della query e dei parametri che decidono i risultati, per
+// rifiutarlo quando non vale più
+pub struct Cursor {
+    pub version: u64,
+    pub params_hash: u64,
+    pub distance: usize,
+    pub index: usize,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        format!(
+            "{:016x}{:016x}{:016x}{:016x}",
+            self.version, self.params_hash, self.distance as u64, self.index as u64
+        )
+    }
+
+    pub fn decode(text: &str) -> Option<Cursor> {
+        if text.len() != 64 || !text.is_ascii() {
+            return None;
+        }
+        let part = |index: usize| u64::from_str_radix(&text[index * 16..][..16], 16).ok();
+
+        Some(Cursor {
+            version: part(0)?,
+            params_hash: part(1)?,
+            distance: usize::try_from(part(2)?).ok()?,
+            index: usize::try_from(part(3)?).ok()?,
+        })
+    }
+}
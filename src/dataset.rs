@@ -0,0 +1,402 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, ErrorKind},
+};
+
+use rayon::prelude::*;
+
+use crate::{
+    config::Config,
+    heap_size::HeapSize,
+    normalize::{fold_case, Normalizer},
+    progress::LoadProgress,
+    timestamp::parse_timestamp,
+    transliterate::Transliterator,
+    vocabulary::Vocabulary,
+};
+
+pub struct Dataset {
+    // sempre almeno una colonna: per i file a una colonna è "name"
+    pub columns: Vec<String>,
+    name_column: usize,
+    delimiter: char,
+    aliases_column: Option<usize>,
+    pub timestamp_column: Option<usize>,
+    normalizer: Normalizer,
+    transliterator: Option<Transliterator>,
+    pub records: Vec<Record>,
+    // incrementata a ogni aggiunta, 0 per i record letti all'avvio
+    pub version: u64,
+    pub vocabulary: Vocabulary,
+}
+
+pub struct Record {
+    pub name: String,
+    // forma usata per il matching, solo se diversa dal nome mostrato
+    pub normalized: Option<String>,
+    // forma minuscola precalcolata per le ricerche case-insensitive
+    pub folded: String,
+    // tutte le colonne della riga, vuoto per i file a una colonna
+    pub fields: Vec<String>,
+    pub aliases: Vec<Alias>,
+    // forma latina del nome, se la traslitterazione è attiva e lo cambia
+    pub transliterated: Option<Alias>,
+    // FNV-1a del contenuto: cambia solo se cambia il record
+    pub hash: u64,
+    // versione del dataset in cui il record è stato aggiunto
+    pub version: u64,
+    // riga del file (da 1, contando anche quelle vuote), `None` se aggiunto via API
+    pub line_number: Option<usize>,
+    // secondi Unix dalla colonna FUZZY_TIMESTAMP_COLUMN, se leggibile
+    pub timestamp: Option<i64>,
+}
+
+// Nome alternativo (da nubile, precedente...) che porta al nome canonico
+pub struct Alias {
+    pub text: String,
+    normalized: Option<String>,
+    folded: String,
+}
+
+impl Dataset {
+    // Dataset senza record, con le impostazioni di `config`
+    pub fn empty(config: &Config) -> Dataset {
+        let (columns, name_column) = if config.columns.is_empty() {
+            (vec!["name".to_string()], 0)
+        } else {
+            let name_column = config
+                .name_column
+                .as_ref()
+                .and_then(|name| config.columns.iter().position(|column| column == name))
+                .unwrap_or(0);
+            (config.columns.clone(), name_column)
+        };
+
+        Dataset {
+            columns,
+            name_column,
+            delimiter: config.delimiter,
+            aliases_column: config
+                .aliases_column
+                .as_ref()
+                .and_then(|name| config.columns.iter().position(|column| column == name)),
+            timestamp_column: config
+                .timestamp_column
+                .as_ref()
+                .and_then(|name| config.columns.iter().position(|column| column == name)),
+            normalizer: Normalizer {
+                dedup_tokens: config.dedup_tokens,
+                strip_suffix: config.strip_suffix.clone(),
+            },
+            transliterator: config.transliterator.clone(),
+            records: Vec::new(),
+            version: 0,
+            vocabulary: Vocabulary::default(),
+        }
+    }
+
+    pub fn load(config: &Config, progress: &LoadProgress) -> std::io::Result<Dataset> {
+        let mut dataset = Dataset::empty(config);
+
+        let file = File::open(&config.names_path)?;
+        progress.start(file.metadata()?.len());
+        let reader = BufReader::with_capacity(READ_BUFFER_BYTES, file);
+        // le righe non UTF-8 si saltano (contano comunque per `line_number`),
+        // un vero errore di lettura interrompe il caricamento e viene restituito
+        let mut read_error = None;
+        let lines = reader
+            .lines()
+            .map_while(|line| match line {
+                Ok(line) => Some(Some(line)),
+                Err(error) if error.kind() == ErrorKind::InvalidData => Some(None),
+                Err(error) => {
+                    read_error = Some(error);
+                    None
+                }
+            })
+            .inspect(|line| progress.line(line.as_ref().map_or(0, |line| line.len() as u64 + 1)))
+            .enumerate()
+            .filter_map(|(index, line)| {
+                if line.is_none() {
+                    eprintln!("Skipping line {}: not valid UTF-8", index + 1);
+                }
+                Some((index, line?))
+            })
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        if config.parallel_load {
+            // la lettura resta sequenziale, parsing e vocabolario no: l'ordine
+            // dei record è preservato e i conteggi dei token si sommano
+            let lines: Vec<(usize, String)> = lines.collect();
+            dataset.records = lines
+                .into_par_iter()
+                .map(|(index, line)| dataset.parse_file_line(index, line))
+                .collect();
+            dataset.vocabulary = dataset
+                .records
+                .par_iter()
+                .fold(Vocabulary::default, |mut vocabulary, record| {
+                    record.add_tokens(&mut vocabulary);
+                    vocabulary
+                })
+                .reduce(Vocabulary::default, Vocabulary::merge);
+        } else {
+            dataset.records = lines
+                .map(|(index, line)| dataset.parse_file_line(index, line))
+                .collect();
+
+            let mut vocabulary = Vocabulary::default();
+            for record in &dataset.records {
+                record.add_tokens(&mut vocabulary);
+            }
+            dataset.vocabulary = vocabulary;
+        }
+
+        match read_error {
+            Some(error) => Err(error),
+            None => Ok(dataset),
+        }
+    }
+
+    // `index` è quello di `enumerate`, quindi da 0
+    fn parse_file_line(&self, index: usize, line: String) -> Record {
+        Record {
+            line_number: Some(index + 1),
+            ..self.parse_line(line)
+        }
+    }
+
+    fn parse_line(&self, line: String) -> Record {
+        let mut record = if self.is_multi_field() {
+            Record::from_fields(
+                split_fields(&line, self.delimiter, self.columns.len()),
+                self.name_column,
+                self.aliases_column,
+                &self.normalizer,
+            )
+        } else {
+            Record::new(line, &self.normalizer)
+        };
+        record.version = self.version;
+        record.timestamp = self
+            .timestamp_column
+            .and_then(|column| parse_timestamp(record.field(column)));
+        record.transliterated = self
+            .transliterator
+            .as_ref()
+            .and_then(|transliterator| transliterator.apply(&record.name))
+            .map(|text| Alias::new(text, &self.normalizer));
+        record
+    }
+
+    // Aggiunge le righe (nello stesso formato del file) come nuova versione
+    pub fn append(&mut self, lines: Vec<String>) -> usize {
+        let lines: Vec<String> = lines
+            .into_iter()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        if lines.is_empty() {
+            return 0;
+        }
+
+        self.version += 1;
+        let added = lines.len();
+        for line in lines {
+            let record = self.parse_line(line);
+            record.add_tokens(&mut self.vocabulary);
+            self.records.push(record);
+        }
+
+        added
+    }
+
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column == name)
+    }
+
+    pub fn is_multi_field(&self) -> bool {
+        self.columns.len() > 1
+    }
+}
+
+impl Record {
+    pub fn new(name: String, normalizer: &Normalizer) -> Self {
+        let hash = fnv1a(name.as_bytes());
+        Record::build(name, Vec::new(), Vec::new(), hash, normalizer)
+    }
+
+    pub fn from_fields(
+        fields: Vec<String>,
+        name_column: usize,
+        aliases_column: Option<usize>,
+        normalizer: &Normalizer,
+    ) -> Self {
+        let hash = fields.iter().fold(FNV_OFFSET_BASIS, |hash, field| {
+            fnv1a_extend(fnv1a_extend(hash, field.as_bytes()), &[0])
+        });
+        let aliases = aliases_column
+            .map(|column| {
+                parse_aliases(&fields[column])
+                    .into_iter()
+                    .map(|alias| Alias::new(alias, normalizer))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Record::build(
+            fields[name_column].clone(),
+            fields,
+            aliases,
+            hash,
+            normalizer,
+        )
+    }
+
+    fn build(
+        name: String,
+        fields: Vec<String>,
+        aliases: Vec<Alias>,
+        hash: u64,
+        normalizer: &Normalizer,
+    ) -> Self {
+        let normalized = normalizer.apply(&name);
+        let folded = fold_case(normalized.as_deref().unwrap_or(&name)).into_owned();
+        Record {
+            name,
+            normalized,
+            folded,
+            fields,
+            aliases,
+            transliterated: None,
+            hash,
+            version: 0,
+            line_number: None,
+            timestamp: None,
+        }
+    }
+
+    pub fn key(&self, ignore_case: bool) -> &str {
+        if ignore_case {
+            &self.folded
+        } else {
+            self.normalized.as_deref().unwrap_or(&self.name)
+        }
+    }
+
+    fn add_tokens(&self, vocabulary: &mut Vocabulary) {
+        vocabulary.add(&self.folded);
+        for alias in self.aliases.iter().chain(&self.transliterated) {
+            vocabulary.add(&alias.folded);
+        }
+    }
+
+    // Per i file a una colonna l'unico campo è il nome
+    pub fn field(&self, index: usize) -> &str {
+        self.fields.get(index).map_or(&self.name, String::as_str)
+    }
+
+    pub fn row_hash(&self) -> String {
+        format!("{:016x}", self.hash)
+    }
+
+    // Tutte le stringhe del record, per il warm-up
+    pub fn strings(&self) -> impl Iterator<Item = &str> {
+        [self.name.as_str(), self.folded.as_str()]
+            .into_iter()
+            .chain(self.normalized.as_deref())
+            .chain(self.fields.iter().map(String::as_str))
+            .chain(
+                self.aliases
+                    .iter()
+                    .chain(&self.transliterated)
+                    .flat_map(Alias::strings),
+            )
+    }
+}
+
+impl Alias {
+    fn new(text: String, normalizer: &Normalizer) -> Self {
+        let normalized = normalizer.apply(&text);
+        let folded = fold_case(normalized.as_deref().unwrap_or(&text)).into_owned();
+        Alias {
+            text,
+            normalized,
+            folded,
+        }
+    }
+
+    pub fn key(&self, ignore_case: bool) -> &str {
+        if ignore_case {
+            &self.folded
+        } else {
+            self.normalized.as_deref().unwrap_or(&self.text)
+        }
+    }
+
+    fn strings(&self) -> impl Iterator<Item = &str> {
+        [self.text.as_str(), self.folded.as_str()]
+            .into_iter()
+            .chain(self.normalized.as_deref())
+    }
+}
+
+impl HeapSize for Record {
+    fn heap_size(&self) -> usize {
+        self.name.heap_size()
+            + self.normalized.heap_size()
+            + self.folded.heap_size()
+            + self.fields.heap_size()
+            + self.aliases.heap_size()
+            + self.transliterated.heap_size()
+    }
+}
+
+impl HeapSize for Alias {
+    fn heap_size(&self) -> usize {
+        self.text.heap_size() + self.normalized.heap_size() + self.folded.heap_size()
+    }
+}
+
+// Alias separati da `|` oppure come array JSON (`["Rossi", "Bianchi"]`)
+fn parse_aliases(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let aliases = if value.starts_with('[') {
+        serde_json::from_str::<Vec<String>>(value).unwrap_or_default()
+    } else {
+        value.split('|').map(str::to_string).collect()
+    };
+
+    aliases
+        .into_iter()
+        .map(|alias| alias.trim().to_string())
+        .filter(|alias| !alias.is_empty())
+        .collect()
+}
+
+// Nessun supporto per le virgolette: i campi mancanti restano vuoti,
+// quelli in eccesso vengono ignorati
+fn split_fields(line: &str, delimiter: char, count: usize) -> Vec<String> {
+    let mut fields: Vec<String> = line
+        .split(delimiter)
+        .take(count)
+        .map(|field| field.trim().to_string())
+        .collect();
+    fields.resize(count, String::new());
+    fields
+}
+
+// Buffer di lettura del file dei nomi
+const READ_BUFFER_BYTES: usize = 1 << 20;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+// Hash stabile tra esecuzioni e versioni di Rust, a differenza di DefaultHasher
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    fnv1a_extend(FNV_OFFSET_BASIS, bytes)
+}
+
+pub fn fnv1a_extend(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
@@ -0,0 +1,469 @@
+use std::{
+    cell::{Cell, RefCell},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::graphemes::graphemes;
+
+// Sopra questa lunghezza (per entrambe le stringhe) la LCS usa Hunt–Szymanski
+const HUNT_SZYMANSKI_MIN_LEN: usize = 64;
+
+pub const DEFAULT_DP_BUFFER_MAX_LEN: usize = 4096;
+
+pub const DEFAULT_NGRAM_SIZE: usize = 3;
+
+// Righe più lunghe di così vengono liberate dopo l'uso invece di restare
+// nel buffer del thread (FUZZY_DP_BUFFER_MAX_LEN)
+static DP_BUFFER_MAX_LEN: AtomicUsize = AtomicUsize::new(DEFAULT_DP_BUFFER_MAX_LEN);
+
+thread_local! {
+    // le due righe della DP, riusate da tutte le chiamate dello stesso thread
+    static DP_ROWS: RefCell<(Vec<usize>, Vec<usize>)> = const { RefCell::new((Vec::new(), Vec::new())) };
+}
+
+thread_local! {
+    // celle DP calcolate da questo thread dall'avvio, per `profile`
+    static DP_CELLS: Cell<u64> = const { Cell::new(0) };
+}
+
+// Un'addizione per chiamata, non per cella; la usa anche `CostMatrix`
+#[inline(always)]
+pub fn count_dp_cells(rows: usize, columns: usize) {
+    DP_CELLS.with(|cells| cells.set(cells.get().wrapping_add((rows * columns) as u64)));
+}
+
+// Contatore del thread corrente: la differenza tra due letture sullo stesso
+// thread è il lavoro fatto nel frattempo
+pub fn dp_cells() -> u64 {
+    DP_CELLS.with(Cell::get)
+}
+
+pub fn set_dp_buffer_max_len(len: usize) {
+    DP_BUFFER_MAX_LEN.store(len, Ordering::Relaxed);
+}
+
+// Presta a `work` due righe azzerate di `width` celle
+fn with_dp_rows<R>(width: usize, work: impl FnOnce(&mut Vec<usize>, &mut Vec<usize>) -> R) -> R {
+    DP_ROWS.with(|rows| {
+        let mut rows = rows.borrow_mut();
+        let (prev, curr) = &mut *rows;
+        prev.clear();
+        prev.resize(width, 0);
+        curr.clear();
+        curr.resize(width, 0);
+
+        let result = work(prev, curr);
+
+        if width > DP_BUFFER_MAX_LEN.load(Ordering::Relaxed) {
+            *prev = Vec::new();
+            *curr = Vec::new();
+        }
+        result
+    })
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    #[default]
+    Levenshtein,
+    Lcs,
+    Subsequence,
+    // Levenshtein con i costi di FUZZY_COST_MATRIX, calcolato da `Search`
+    Weighted,
+    // Levenshtein solo sui token con lo stesso Soundex della query, filtrati da `Search`
+    #[serde(rename = "phonetic_fuzzy")]
+    PhoneticFuzzy,
+    // Levenshtein tra la query e la finestra più vicina del token
+    #[serde(rename = "fuzzy_contains")]
+    FuzzyContains,
+    // n-grammi della query che mancano nel candidato intero
+    #[serde(rename = "ngram_containment")]
+    NgramContainment,
+}
+
+impl Algorithm {
+    // Distanza per grapheme cluster: un carattere visivo (emoji composta,
+    // lettera con accento combinante) è una sola unità di modifica
+    pub fn grapheme_distance(self, a: &str, b: &str) -> usize {
+        let a = graphemes(a);
+        let b = graphemes(b);
+        match self {
+            Algorithm::Lcs => a.len() + b.len() - 2 * lcs_len_dp(&a, &b),
+            // `Search` accetta `graphemes` solo con levenshtein e lcs
+            _ => calc_dist(&a, &b),
+        }
+    }
+
+    #[inline(always)]
+    pub fn distance(self, a: &[u8], b: &[u8]) -> usize {
+        match self {
+            // senza matrice i costi sono tutti 1, cioè Levenshtein
+            Algorithm::Levenshtein | Algorithm::Weighted | Algorithm::PhoneticFuzzy => {
+                calc_dist_bytes(a, b)
+            }
+            Algorithm::Lcs => lcs_distance(a, b),
+            Algorithm::FuzzyContains => sellers(a, b, true).0,
+            // `Search` prepara gli n-grammi della query una volta sola
+            Algorithm::NgramContainment => match (std::str::from_utf8(a), std::str::from_utf8(b)) {
+                (Ok(a), Ok(b)) => missing_ngrams(&ngrams(a, DEFAULT_NGRAM_SIZE), b),
+                _ => usize::MAX,
+            },
+            Algorithm::Subsequence => {
+                subsequence_window(a, b).map_or(usize::MAX, |(start, end)| end - start - a.len())
+            }
+        }
+    }
+}
+
+// Scomposizione della distanza nelle operazioni che trasformano la query nel
+// testo trovato; le trasposizioni restano a 0 finché non c'è un algoritmo OSA
+#[derive(Serialize, Debug, Default, Clone, Copy)]
+pub struct EditOps {
+    pub insertions: usize,
+    pub deletions: usize,
+    pub substitutions: usize,
+    pub transpositions: usize,
+}
+
+impl EditOps {
+    pub fn new(algorithm: Algorithm, a: &[u8], b: &[u8]) -> EditOps {
+        match algorithm {
+            Algorithm::Levenshtein | Algorithm::Weighted | Algorithm::PhoneticFuzzy => {
+                levenshtein_ops(a, b)
+            }
+            // senza sostituzioni i conteggi dipendono solo dalla LCS
+            Algorithm::Lcs => {
+                let lcs = (a.len() + b.len() - lcs_distance(a, b)) / 2;
+                EditOps {
+                    insertions: b.len() - lcs,
+                    deletions: a.len() - lcs,
+                    ..EditOps::default()
+                }
+            }
+            // le operazioni per arrivare alla finestra, il resto del token è gratis
+            Algorithm::FuzzyContains => {
+                let (start, end) = fuzzy_contains_window(a, b);
+                levenshtein_ops(a, &b[start..end])
+            }
+            // non è una distanza di modifica: `Search` rifiuta `op_counts`
+            Algorithm::NgramContainment => EditOps::default(),
+            // i buchi della finestra sono caratteri inseriti tra quelli della query
+            Algorithm::Subsequence => EditOps {
+                insertions: algorithm.distance(a, b),
+                ..EditOps::default()
+            },
+        }
+    }
+}
+
+// Matrice completa e backtrack dall'angolo in basso a destra; a parità di
+// costo si preferisce la diagonale, così l'allineamento è deterministico
+fn levenshtein_ops(a: &[u8], b: &[u8]) -> EditOps {
+    let width = b.len() + 1;
+    let mut matrix = vec![0; (a.len() + 1) * width];
+    for (j, cell) in matrix.iter_mut().take(width).enumerate() {
+        *cell = j;
+    }
+
+    for (i, &byte_a) in a.iter().enumerate() {
+        let row = (i + 1) * width;
+        matrix[row] = i + 1;
+
+        for (j, &byte_b) in b.iter().enumerate() {
+            let cost = usize::from(byte_a != byte_b);
+            matrix[row + j + 1] = (matrix[row - width + j + 1] + 1)
+                .min(matrix[row + j] + 1)
+                .min(matrix[row - width + j] + cost);
+        }
+    }
+
+    count_dp_cells(a.len(), b.len());
+    let mut ops = EditOps::default();
+    let (mut i, mut j) = (a.len(), b.len());
+    while i > 0 || j > 0 {
+        let here = matrix[i * width + j];
+        if i > 0 && j > 0 {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            if here == matrix[(i - 1) * width + j - 1] + cost {
+                ops.substitutions += cost;
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+        if i > 0 && here == matrix[(i - 1) * width + j] + 1 {
+            ops.deletions += 1;
+            i -= 1;
+        } else {
+            ops.insertions += 1;
+            j -= 1;
+        }
+    }
+
+    ops
+}
+
+#[inline(always)]
+pub fn calc_dist_bytes(a: &[u8], b: &[u8]) -> usize {
+    calc_dist(a, b)
+}
+
+// Stessa DP su unità qualsiasi: byte, o grapheme cluster con `graphemes`
+#[inline(always)]
+pub fn calc_dist<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let len_a = a.len();
+    let len_b = b.len();
+
+    if len_a == 0 {
+        return len_b;
+    }
+    if len_b == 0 {
+        return len_a;
+    }
+
+    count_dp_cells(len_a, len_b);
+    with_dp_rows(len_b + 1, |prev, curr| {
+        for (j, cell) in prev.iter_mut().enumerate() {
+            *cell = j;
+        }
+
+        for (i, byte_a) in a.iter().enumerate() {
+            curr[0] = i + 1;
+
+            for j in 0..len_b {
+                let cost = if *byte_a == b[j] { 0 } else { 1 };
+
+                curr[j + 1] = (prev[j + 1] + 1).min(curr[j] + 1).min(prev[j] + cost);
+            }
+
+            std::mem::swap(prev, curr);
+        }
+
+        prev[len_b]
+    })
+}
+
+// N-grammi distinti di caratteri di `text`; se `text` è più corto di `n`
+// l'unico n-gramma è il testo intero
+pub fn ngrams(text: &str, n: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= n {
+        return vec![text.to_string()];
+    }
+
+    let mut grams: Vec<String> = chars
+        .windows(n)
+        .map(|window| window.iter().collect())
+        .collect();
+    grams.sort_unstable();
+    grams.dedup();
+    grams
+}
+
+// Quanti degli n-grammi non compaiono in `candidate`
+pub fn missing_ngrams(grams: &[String], candidate: &str) -> usize {
+    grams
+        .iter()
+        .filter(|gram| !candidate.contains(gram.as_str()))
+        .count()
+}
+
+// Algoritmo di Sellers: la DP di Levenshtein con l'ultima riga letta tutta, così i
+// caratteri di `b` dopo la finestra sono gratis, e con `free_start` anche la prima
+// riga a zero, così lo sono anche quelli prima. Restituisce la distanza e dove
+// finisce (esclusa) la prima finestra migliore.
+fn sellers(a: &[u8], b: &[u8], free_start: bool) -> (usize, usize) {
+    let len_b = b.len();
+    if a.is_empty() {
+        return (0, 0);
+    }
+
+    count_dp_cells(a.len(), len_b);
+    with_dp_rows(len_b + 1, |prev, curr| {
+        if !free_start {
+            for (j, cell) in prev.iter_mut().enumerate() {
+                *cell = j;
+            }
+        }
+
+        for (i, byte_a) in a.iter().enumerate() {
+            curr[0] = i + 1;
+
+            for j in 0..len_b {
+                let cost = usize::from(*byte_a != b[j]);
+                curr[j + 1] = (prev[j + 1] + 1).min(curr[j] + 1).min(prev[j] + cost);
+            }
+
+            std::mem::swap(prev, curr);
+        }
+
+        prev.iter()
+            .enumerate()
+            .min_by_key(|&(_, &distance)| distance)
+            .map(|(end, &distance)| (distance, end))
+            .unwrap_or((a.len(), 0))
+    })
+}
+
+// Finestra `start..end` di `b` alla distanza minima da `a`: la fine viene dalla
+// passata in avanti, l'inizio da una all'indietro ancorata alla fine
+fn fuzzy_contains_window(a: &[u8], b: &[u8]) -> (usize, usize) {
+    let (_, end) = sellers(a, b, true);
+    let reversed_a: Vec<u8> = a.iter().rev().copied().collect();
+    let reversed_b: Vec<u8> = b[..end].iter().rev().copied().collect();
+    let (_, len) = sellers(&reversed_a, &reversed_b, false);
+    (end - len, end)
+}
+
+// Levenshtein con gli spazi come ancore: uno spazio può solo allinearsi a un
+// altro spazio, mai essere sostituito, cancellato o inserito. Così l'allineamento
+// rispetta i confini tra le parole ("john smith" non diventa "johnsmith").
+// `None` se le due stringhe non hanno lo stesso numero di spazi allineabili.
+pub fn calc_dist_word_boundaries(a: &[u8], b: &[u8]) -> Option<usize> {
+    let len_b = b.len();
+    // più grande di qualunque distanza reale, senza rischio di overflow
+    let blocked = a.len() + len_b + 1;
+    let edit_cost = |byte: u8| if byte == b' ' { blocked } else { 1 };
+
+    let mut prev = Vec::with_capacity(len_b + 1);
+    prev.push(0);
+    for &byte_b in b {
+        let last = prev[prev.len() - 1];
+        prev.push((last + edit_cost(byte_b)).min(blocked));
+    }
+    let mut curr = vec![0; len_b + 1];
+
+    for &byte_a in a {
+        curr[0] = (prev[0] + edit_cost(byte_a)).min(blocked);
+
+        for j in 0..len_b {
+            let byte_b = b[j];
+            let substitution = match (byte_a == b' ', byte_b == b' ') {
+                (true, true) => 0,
+                (false, false) => usize::from(byte_a != byte_b),
+                _ => blocked,
+            };
+
+            curr[j + 1] = (prev[j + 1] + edit_cost(byte_a))
+                .min(curr[j] + edit_cost(byte_b))
+                .min(prev[j] + substitution)
+                .min(blocked);
+        }
+
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    count_dp_cells(a.len(), len_b);
+    (prev[len_b] < blocked).then_some(prev[len_b])
+}
+
+// Distanza con sole inserzioni e cancellazioni: len(a) + len(b) - 2 * LCS
+pub fn lcs_distance(a: &[u8], b: &[u8]) -> usize {
+    let lcs = if a.len() > HUNT_SZYMANSKI_MIN_LEN && b.len() > HUNT_SZYMANSKI_MIN_LEN {
+        lcs_len_hunt_szymanski(a, b)
+    } else {
+        lcs_len_dp(a, b)
+    };
+
+    a.len() + b.len() - 2 * lcs
+}
+
+pub fn lcs_len_dp<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let len_b = b.len();
+    count_dp_cells(a.len(), len_b);
+
+    with_dp_rows(len_b + 1, |prev, curr| {
+        for byte_a in a {
+            for j in 0..len_b {
+                curr[j + 1] = if *byte_a == b[j] {
+                    prev[j] + 1
+                } else {
+                    prev[j + 1].max(curr[j])
+                };
+            }
+
+            std::mem::swap(prev, curr);
+        }
+
+        prev[len_b]
+    })
+}
+
+// Nessuna matrice: per ogni byte di `a` si scorrono le sue occorrenze in `b`
+// (da destra) e si aggiornano le soglie minime delle sottosequenze crescenti.
+// Costa O((r + n) log n) con r = numero di coppie uguali, quindi conviene su
+// stringhe lunghe e molto simili.
+pub fn lcs_len_hunt_szymanski(a: &[u8], b: &[u8]) -> usize {
+    let mut positions: [Vec<usize>; 256] = std::array::from_fn(|_| Vec::new());
+    for (j, &byte) in b.iter().enumerate() {
+        positions[byte as usize].push(j);
+    }
+
+    let mut thresholds: Vec<usize> = Vec::with_capacity(a.len().min(b.len()));
+    for &byte in a {
+        for &j in positions[byte as usize].iter().rev() {
+            let k = thresholds.partition_point(|&t| t < j);
+            if k == thresholds.len() {
+                thresholds.push(j);
+            } else {
+                thresholds[k] = j;
+            }
+        }
+    }
+
+    thresholds.len()
+}
+
+// Finestra più corta di `candidate` che contiene `query` come sottosequenza,
+// come intervallo [start, end). I caratteri della finestra non usati dalla
+// query sono i "buchi"; la distanza in modalità subsequence è il loro numero.
+pub fn subsequence_window(query: &[u8], candidate: &[u8]) -> Option<(usize, usize)> {
+    if query.is_empty() {
+        return Some((0, 0));
+    }
+
+    let mut best: Option<(usize, usize)> = None;
+    let mut from = 0;
+
+    while from < candidate.len() {
+        // in avanti: prima occorrenza completa della query a partire da `from`
+        let mut matched = 0;
+        let mut end = from;
+        while end < candidate.len() {
+            if candidate[end] == query[matched] {
+                matched += 1;
+                if matched == query.len() {
+                    break;
+                }
+            }
+            end += 1;
+        }
+        if matched < query.len() {
+            break;
+        }
+
+        // all'indietro: l'inizio più vicino che chiude la stessa occorrenza
+        let mut start = end;
+        let mut remaining = query.len();
+        loop {
+            if candidate[start] == query[remaining - 1] {
+                remaining -= 1;
+                if remaining == 0 {
+                    break;
+                }
+            }
+            start -= 1;
+        }
+
+        let window = (start, end + 1);
+        if best.is_none_or(|(best_start, best_end)| window.1 - window.0 < best_end - best_start) {
+            best = Some(window);
+        }
+        from = start + 1;
+    }
+
+    best
+}
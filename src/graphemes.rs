@@ -0,0 +1,64 @@
+// Segmentazione approssimata in grapheme cluster, senza tabelle Unicode
+// complete: copre i casi che contano per i nomi (lettere con segni
+// combinanti, emoji con ZWJ, modificatori e selettori di variante,
+// bandiere e bandiere a tag, CR LF). Non gestisce jamo hangul né i cluster
+// indici con virama.
+pub fn graphemes(text: &str) -> Vec<&str> {
+    let mut clusters = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+    // indicatori regionali nel cluster corrente: una bandiera ne ha due
+    let mut regional = 0;
+
+    for (index, current) in text.char_indices() {
+        let joins = match previous {
+            None => false,
+            Some('\r') => current == '\n',
+            Some('\u{200D}') => true,
+            Some(previous) => {
+                is_extender(current)
+                    || is_regional_indicator(previous)
+                        && is_regional_indicator(current)
+                        && regional % 2 == 1
+            }
+        };
+
+        if !joins && index > 0 {
+            clusters.push(&text[start..index]);
+            start = index;
+            regional = 0;
+        }
+        if is_regional_indicator(current) {
+            regional += 1;
+        }
+        previous = Some(current);
+    }
+
+    if start < text.len() {
+        clusters.push(&text[start..]);
+    }
+    clusters
+}
+
+// Caratteri che non iniziano mai un cluster ma si attaccano al precedente
+fn is_extender(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'       // segni diacritici combinanti
+        | '\u{0483}'..='\u{0489}'     // cirillico
+        | '\u{0591}'..='\u{05BD}'     // ebraico
+        | '\u{064B}'..='\u{065F}'     // arabo
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{200C}'..='\u{200D}'     // ZWNJ, ZWJ
+        | '\u{20D0}'..='\u{20FF}'     // segni combinanti per simboli (keycap)
+        | '\u{FE00}'..='\u{FE0F}'     // selettori di variante
+        | '\u{FE20}'..='\u{FE2F}'
+        | '\u{1F3FB}'..='\u{1F3FF}'   // colori della pelle
+        | '\u{E0020}'..='\u{E007F}'   // tag delle bandiere regionali
+        | '\u{E0100}'..='\u{E01EF}'   // selettori di variante supplementari
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c, '\u{1F1E6}'..='\u{1F1FF}')
+}
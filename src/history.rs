@@ -0,0 +1,93 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use serde::Serialize;
+
+use crate::normalize::fold_case;
+
+// Ultime `capacity` query cercate, in un buffer circolare, con il conteggio di
+// ciascuna: una query esce dal conteggio quando esce dal buffer, quindi le
+// query distinte sono al massimo `capacity`. Con `capacity` 0 non registra nulla.
+pub struct SearchHistory {
+    capacity: usize,
+    state: Mutex<HistoryState>,
+}
+
+#[derive(Default)]
+struct HistoryState {
+    recent: VecDeque<String>,
+    counts: HashMap<String, usize>,
+}
+
+#[derive(Serialize)]
+pub struct Suggestion {
+    pub query: String,
+    pub count: usize,
+}
+
+impl SearchHistory {
+    pub fn new(capacity: usize) -> SearchHistory {
+        SearchHistory {
+            capacity,
+            state: Mutex::new(HistoryState::default()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    // Le query sono registrate in minuscolo e con gli spazi compattati, così
+    // "Mario " e "mario" contano insieme
+    pub fn record(&self, query: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+        let query = normalize_query(query);
+        if query.is_empty() {
+            return;
+        }
+
+        let mut state = self.state.lock().expect("history lock poisoned");
+        if state.recent.len() == self.capacity {
+            if let Some(oldest) = state.recent.pop_front() {
+                if let Some(count) = state.counts.get_mut(&oldest) {
+                    *count -= 1;
+                    if *count == 0 {
+                        state.counts.remove(&oldest);
+                    }
+                }
+            }
+        }
+        *state.counts.entry(query.clone()).or_insert(0) += 1;
+        state.recent.push_back(query);
+    }
+
+    // Le query più frequenti che iniziano con `prefix`; a parità di conteggio
+    // in ordine alfabetico
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<Suggestion> {
+        let prefix = normalize_query(prefix);
+        let state = self.state.lock().expect("history lock poisoned");
+        let mut suggestions: Vec<Suggestion> = state
+            .counts
+            .iter()
+            .filter(|(query, _)| query.starts_with(&prefix))
+            .map(|(query, &count)| Suggestion {
+                query: query.clone(),
+                count,
+            })
+            .collect();
+        suggestions.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.query.cmp(&b.query)));
+        suggestions.truncate(limit);
+        suggestions
+    }
+}
+
+fn normalize_query(query: &str) -> String {
+    fold_case(query)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
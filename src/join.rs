@@ -0,0 +1,75 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde_json::{Map, Value};
+
+// Attributi extra per i risultati (FUZZY_JOIN_PATH), per chiave esatta: un
+// oggetto JSON `{"chiave": {"attributo": valore, ...}}` oppure un CSV con
+// intestazione, la chiave nella prima colonna
+pub struct JoinTable {
+    // unione degli attributi di tutte le righe, in ordine alfabetico
+    attributes: BTreeSet<String>,
+    rows: HashMap<String, Map<String, Value>>,
+}
+
+impl JoinTable {
+    pub fn load(path: &str) -> Result<JoinTable, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| format!("cannot read FUZZY_JOIN_PATH `{path}`: {error}"))?;
+        let rows = if path.ends_with(".json") {
+            serde_json::from_str(&text)
+                .map_err(|error| format!("invalid FUZZY_JOIN_PATH `{path}`: {error}"))?
+        } else {
+            parse_csv(&text).ok_or_else(|| format!("FUZZY_JOIN_PATH `{path}` has no header"))?
+        };
+
+        let attributes = rows
+            .values()
+            .flat_map(|row: &Map<String, Value>| row.keys().cloned())
+            .collect();
+        Ok(JoinTable { attributes, rows })
+    }
+
+    // Tutti gli attributi, a `null` quelli che la riga non ha o tutti se la
+    // chiave manca, così ogni risultato ha la stessa forma
+    pub fn lookup(&self, key: &str) -> BTreeMap<String, Value> {
+        let row = self.rows.get(key);
+        self.attributes
+            .iter()
+            .map(|attribute| {
+                let value = row
+                    .and_then(|row| row.get(attribute))
+                    .cloned()
+                    .unwrap_or(Value::Null);
+                (attribute.clone(), value)
+            })
+            .collect()
+    }
+}
+
+// Separato da virgole, senza virgolette come il file dei nomi; le celle vuote
+// diventano `null` e per una chiave ripetuta vale l'ultima riga
+fn parse_csv(text: &str) -> Option<HashMap<String, Map<String, Value>>> {
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<&str> = lines.next()?.split(',').map(str::trim).collect();
+
+    let rows = lines
+        .map(|line| {
+            let mut cells = line.split(',').map(str::trim);
+            let key = cells.next().unwrap_or_default().to_string();
+            let row = header[1..]
+                .iter()
+                .zip(cells.chain(std::iter::repeat("")))
+                .map(|(attribute, cell)| {
+                    let value = if cell.is_empty() {
+                        Value::Null
+                    } else {
+                        Value::String(cell.to_string())
+                    };
+                    (attribute.to_string(), value)
+                })
+                .collect();
+            (key, row)
+        })
+        .collect();
+    Some(rows)
+}
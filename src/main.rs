@@ -1,36 +1,142 @@
+mod config;
+mod cost_matrix;
+mod cursor;
+mod dataset;
+mod distance;
+mod graphemes;
+mod heap_size;
+mod history;
+mod join;
+mod normalize;
+mod phonetic;
+mod pools;
+mod prefix_tree;
+mod progress;
+mod request_id;
+mod search;
+mod timestamp;
+mod transliterate;
+mod vocabulary;
+mod warm_up;
+mod ws;
+
 use std::{
-    fs::File,
-    io::{BufRead, Read},
+    collections::BTreeMap,
+    sync::{OnceLock, RwLock},
     time::Instant,
 };
 
-use rayon::prelude::*;
+use futures_util::{future::ready, stream, StreamExt};
 
 use actix_web::{
-    web::{get, post, Data, Json},
-    App, HttpResponse, HttpServer,
+    error::ErrorInternalServerError,
+    rt::task::spawn_blocking,
+    web::{get, post, Bytes, Data, Json, Query, ReqData},
+    App, Error, HttpRequest, HttpResponse, HttpServer,
 };
 use serde::{Deserialize, Serialize};
 
+use config::{Config, OversizePolicy};
+use dataset::Dataset;
+use heap_size::HeapSize;
+use history::{SearchHistory, Suggestion};
+use pools::ThreadPools;
+use prefix_tree::{prefix_tree, PrefixNode};
+use progress::LoadProgress;
+use request_id::RequestId;
+use search::{Arm, GroupBy, Search, SearchParams, SearchResult};
+use warm_up::{warm_up, WarmUpReport};
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    // Leggi il file una sola volta all'avvio
-    let names = get_names()?;
-    let names_data = Data::new(names);
+    let config = Config::from_env()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+    distance::set_dp_buffer_max_len(config.dp_buffer_max_len);
+    search::set_score_precision(config.score_precision);
+
+    let progress_data = Data::new(LoadProgress::new(config.progress_every));
+    let warm_up_data = Data::new(OnceLock::new());
+    let dataset_data = Data::new(RwLock::new(Dataset::empty(&config)));
+    let bind = config.bind.clone();
+    let config_data = Data::new(config);
+    let pools_data = Data::new(ThreadPools::default());
+    let history_data = Data::new(SearchHistory::new(config_data.history_size));
+
+    // Leggi il file una sola volta all'avvio, oppure in background mentre il
+    // server risponde già (503 finché non ha finito)
+    let load = {
+        let config = config_data.clone();
+        let dataset_data = dataset_data.clone();
+        let progress = progress_data.clone();
+        let warm_up_data = warm_up_data.clone();
+        move || load_dataset(&config, &dataset_data, &progress, &warm_up_data)
+    };
+    if config_data.background_load {
+        std::thread::spawn(move || {
+            if let Err(error) = load() {
+                eprintln!("Loading failed: {error}");
+                std::process::exit(1);
+            }
+        });
+    } else {
+        load()?;
+    }
+    let slow_query_ms = config_data.slow_query_ms;
 
     let server = HttpServer::new(move || {
         App::new()
-            .app_data(names_data.clone())
+            .app_data(dataset_data.clone())
+            .app_data(config_data.clone())
+            .app_data(pools_data.clone())
+            .app_data(warm_up_data.clone())
+            .app_data(progress_data.clone())
+            .app_data(history_data.clone())
+            .wrap_fn(move |request, service| request_id::propagate(request, service, slow_query_ms))
             .route("/", get().to(get_index))
             .route("/search", post().to(post_search))
+            .route("/names", post().to(post_names))
+            .route("/stats", get().to(get_stats))
+            .route("/ready", get().to(get_ready))
+            .route("/suggest", get().to(get_suggest))
+            .route("/config", get().to(get_config))
+            .route("/ws", get().to(ws::get_ws))
     });
 
-    println!("Serving on http://localhost:3000...");
-    server.bind("127.0.0.1:3000")?.run().await?;
+    println!("Serving on http://{bind}...");
+    server.bind(&bind)?.run().await?;
 
     Ok(())
 }
 
+fn load_dataset(
+    config: &Config,
+    dataset_data: &RwLock<Dataset>,
+    progress: &LoadProgress,
+    warm_up_data: &OnceLock<WarmUpReport>,
+) -> std::io::Result<()> {
+    let load_time = Instant::now();
+    let dataset = Dataset::load(config, progress)?;
+    println!(
+        "Loaded {} records in {} ms",
+        dataset.records.len(),
+        load_time.elapsed().as_millis()
+    );
+
+    if config.warm_up || config.mlock {
+        let report = warm_up(&dataset, config.mlock);
+        println!(
+            "Warm-up completed in {} ms (memory locked: {})",
+            report.duration_ms, report.memory_locked
+        );
+        let _ = warm_up_data.set(report);
+    }
+
+    *dataset_data.write().expect("dataset lock poisoned") = dataset;
+    progress.finish();
+    Ok(())
+}
+
 async fn get_index() -> HttpResponse {
     HttpResponse::Ok().content_type("text/html").body(
         r#"
@@ -123,103 +229,658 @@ async fn get_index() -> HttpResponse {
     )
 }
 
-#[derive(Deserialize)]
-struct SearchParams {
-    query: String,
+#[derive(Serialize)]
+struct SearchResponse {
+    results: Vec<SearchResult>,
+    // assente con `seed`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_time: Option<u64>,
+    version: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parallelism: Option<usize>,
+    #[serde(skip_serializing_if = "is_false")]
+    truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggestion: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    autocorrect: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "is_false")]
+    sampled: bool,
+    // seme effettivo delle componenti casuali, per poterle riprodurre
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "search::serialize_rounded_option"
+    )]
+    rank_confidence: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arm: Option<Arm>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    canary_fallback: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    facets: Option<BTreeMap<String, usize>>,
+    #[serde(skip_serializing_if = "is_false")]
+    rejected: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warning: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tree: Option<PrefixNode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dp_cells: Option<u64>,
+    #[serde(skip_serializing_if = "is_false")]
+    fuzzy_skipped: bool,
+}
+
+#[derive(Serialize)]
+struct StreamChunk {
+    stage: &'static str,
+    results: Vec<SearchResult>,
+    // assente con `seed`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_time: Option<u64>,
+    version: u64,
+    #[serde(skip_serializing_if = "is_false")]
+    truncated: bool,
+    // solo nella riga `exact`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggestion: Option<String>,
+    // solo nella riga `exact`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arm: Option<Arm>,
+    // solo nella riga `exact`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    canary_fallback: Option<String>,
+    // solo nella riga `exact`
+    #[serde(skip_serializing_if = "is_false")]
+    rejected: bool,
+    // solo nella riga `exact`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warning: Option<String>,
+    // solo nella riga `fuzzy`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    autocorrect: Option<String>,
 }
 
-#[derive(Serialize, Debug)]
-struct SearchResult {
-    name: String,
-    distance: usize,
+fn is_false(value: &bool) -> bool {
+    !value
 }
 
 #[derive(Serialize)]
-struct SearchResponse {
-    results: Vec<SearchResult>,
-    response_time: u64,
+struct ErrorResponse {
+    error: String,
 }
 
-async fn post_search(params: Json<SearchParams>, names: Data<Vec<String>>) -> HttpResponse {
-    let start_time = Instant::now();
+fn bad_request(error: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(ErrorResponse { error })
+}
 
-    let query = params.query.as_str();
-    let query_bytes = query.as_bytes();
-
-    let mut results: Vec<SearchResult> = names
-        .par_iter()
-        .filter_map(|name| {
-            let distance = fuzzy_match(query_bytes, name);
-            if distance < 3 {
-                Some(SearchResult {
-                    name: name.clone(),
-                    distance,
-                })
-            } else {
-                None
-            }
+// Byte riservati ai campi fissi della risposta fuori da `results`
+const RESPONSE_ENVELOPE_BYTES: usize = 128;
+
+// Campi della risposta che non dipendono da quali risultati restano, stimati
+// come `SearchResult::estimated_size`
+fn reserved_size(
+    facets: Option<&BTreeMap<String, usize>>,
+    suggestion: Option<&str>,
+    warning: Option<&str>,
+) -> usize {
+    facets.map_or(0, |facets| {
+        12 + facets.keys().map(|value| value.len() + 24).sum::<usize>()
+    }) + suggestion.map_or(0, |text| text.len() + 16)
+        + warning.map_or(0, |text| text.len() + 16)
+}
+
+// Un albero dei prefissi ha al più due nodi per risultato, con un prefisso non
+// più lungo del nome, più l'indice del risultato
+fn tree_size(result: &SearchResult) -> usize {
+    2 * (48 + result.name.len()) + 8
+}
+
+// Controlla la dimensione stimata della risposta rispetto al budget: con
+// `Truncate` scarta i risultati in eccesso e restituisce `Ok(true)`, con
+// `Reject` restituisce la dimensione stimata come errore. `reserved` sono i
+// byte degli altri campi; se da soli superano il budget anche `Truncate`
+// restituisce l'errore, perché togliere risultati non basta.
+fn apply_size_guard(
+    results: &mut Vec<SearchResult>,
+    budget: usize,
+    policy: OversizePolicy,
+    reserved: usize,
+    with_tree: bool,
+) -> Result<bool, usize> {
+    let size = |result: &SearchResult| {
+        result.estimated_size() + if with_tree { tree_size(result) } else { 0 }
+    };
+    let mut total = RESPONSE_ENVELOPE_BYTES + reserved;
+    let fitting = results
+        .iter()
+        .take_while(|result| {
+            total += size(result);
+            total <= budget
         })
-        .collect();
+        .count();
+
+    if fitting == results.len() && total <= budget {
+        return Ok(false);
+    }
+
+    match policy {
+        OversizePolicy::Truncate if RESPONSE_ENVELOPE_BYTES + reserved <= budget => {
+            results.truncate(fitting);
+            Ok(true)
+        }
+        _ => Err(RESPONSE_ENVELOPE_BYTES + reserved + results.iter().map(size).sum::<usize>()),
+    }
+}
+
+fn payload_too_large(size: usize, config: &Config) -> HttpResponse {
+    HttpResponse::PayloadTooLarge().json(ErrorResponse {
+        error: oversize_message(size, config),
+    })
+}
+
+fn oversize_message(size: usize, config: &Config) -> String {
+    format!(
+        "response would be about {size} bytes, over the {} byte limit: \
+         lower `limit` or `max_distance`",
+        config.max_response_bytes
+    )
+}
+
+// `X-Canary: 1` o `X-Canary: 0` forzano il braccio, ignorando l'hash della query
+fn forced_arm(request: &HttpRequest) -> Option<Arm> {
+    match request.headers().get("x-canary")?.to_str().ok()? {
+        "1" | "true" | "yes" => Some(Arm::Canary),
+        "0" | "false" | "no" => Some(Arm::Control),
+        _ => None,
+    }
+}
+
+async fn post_search(
+    request: HttpRequest,
+    params: Json<SearchParams>,
+    dataset_data: Data<RwLock<Dataset>>,
+    config: Data<Config>,
+    pools: Data<ThreadPools>,
+    request_id: ReqData<RequestId>,
+    progress: Data<LoadProgress>,
+) -> HttpResponse {
+    if !progress.is_loaded() {
+        return still_loading(&progress);
+    }
+
+    let dataset = dataset_data.read().expect("dataset lock poisoned");
+    let mut params = params.into_inner();
+    params.forced_arm = forced_arm(&request);
+    let search = match Search::new(params, &dataset, &config) {
+        Ok(search) => search,
+        Err(error) => return bad_request(error),
+    };
+    // dall'app invece che come estrattore, per non allungare ancora la firma;
+    // solo le prime pagine, altrimenti sfogliare i risultati gonfia il conteggio
+    if let Some(history) = request
+        .app_data::<Data<SearchHistory>>()
+        .filter(|_| search.params.cursor.is_none())
+    {
+        history.record(&search.params.query);
+    }
+
+    if search.params.stream {
+        drop(dataset);
+        return stream_search(search, dataset_data, config, pools, request_id.into_inner());
+    }
+
+    match run_search(&search, &dataset, &config, &pools) {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(size) => payload_too_large(size, &config),
+    }
+}
+
+// Ricerca completa, non in streaming; l'errore è la dimensione stimata della
+// risposta quando supera il limite con `OversizePolicy::Reject`
+fn run_search(
+    search: &Search,
+    dataset: &Dataset,
+    config: &Config,
+    pools: &ThreadPools,
+) -> Result<SearchResponse, usize> {
+    let start_time = Instant::now();
+
+    // con `early_abort` prima il passaggio economico dei match esatti, quello
+    // dello streaming; la scansione fuzzy solo se non bastano
+    let exact = search
+        .params
+        .early_abort
+        .then(|| pools.install(search.parallelism, || search.exact_matches(dataset)))
+        .filter(|exact| exact.len() >= search.limit);
+    let fuzzy_skipped = exact.is_some();
+    let mut results = exact.unwrap_or_else(|| {
+        pools.install(search.parallelism, || search.fuzzy_matches(dataset, false))
+    });
 
+    // a parità di distanza resta l'ordine del dataset, come vuole il cursore
     results.sort_by_key(|item| item.distance);
-    let response_time = start_time.elapsed().as_millis() as u64;
+    // dopo uno stop anticipato potrebbero esserci match fuzzy oltre la pagina
+    let has_more = results.len() > search.limit || fuzzy_skipped;
+    let facets = search.facets(dataset, &results);
+    let autocorrect = search
+        .params
+        .autocorrect
+        .then(|| search.autocorrect(&results))
+        .flatten();
+    let sampled = match (search.params.sample, search.params.diversity) {
+        (true, _) => search.sample(&mut results),
+        (false, Some(weight)) => {
+            search.diversify(&mut results, weight);
+            false
+        }
+        (false, None) => {
+            results.truncate(search.limit);
+            false
+        }
+    };
 
-    HttpResponse::Ok().json(SearchResponse {
-        results: results.into_iter().take(10).collect(),
+    let suggestion = search
+        .params
+        .suggest
+        .then(|| search.suggestion(dataset))
+        .flatten();
+    let truncated = apply_size_guard(
+        &mut results,
+        config.max_response_bytes,
+        config.oversize_policy,
+        reserved_size(
+            facets.as_ref(),
+            suggestion.as_deref(),
+            search.warning.as_deref(),
+        ),
+        search.params.group_by.is_some(),
+    )?;
+    // un campione o un riordino non hanno una "pagina successiva"
+    let next_cursor =
+        if (has_more || truncated) && !search.params.sample && search.params.diversity.is_none() {
+            search.next_cursor(&results, dataset.version)
+        } else {
+            None
+        };
+    let rank_confidence = search
+        .params
+        .rank_confidence
+        .then(|| search::rank_confidence(&results))
+        .flatten();
+    let tree = search
+        .params
+        .group_by
+        .map(|GroupBy::PrefixTree| prefix_tree(results.iter().map(|result| result.name.as_str())));
+    // l'unico campo che dipende dall'orologio: senza, una richiesta con seed
+    // dà sempre la stessa risposta byte per byte
+    let response_time = search
+        .seed
+        .is_none()
+        .then(|| start_time.elapsed().as_millis() as u64);
+
+    Ok(SearchResponse {
+        results,
         response_time,
+        version: dataset.version,
+        parallelism: search.parallelism,
+        truncated,
+        suggestion,
+        autocorrect,
+        next_cursor,
+        sampled,
+        seed: search.seed,
+        rank_confidence,
+        arm: search.arm,
+        canary_fallback: search.canary_fallback.clone(),
+        facets,
+        rejected: search.rejected,
+        warning: search.warning.clone(),
+        tree,
+        dp_cells: search.dp_cells(),
+        fuzzy_skipped,
     })
 }
 
-fn get_names() -> std::io::Result<Vec<String>> {
-    let file = File::open("./names.csv")?;
-    let reader = std::io::BufReader::new(file);
-    let names = reader
-        .lines()
-        .filter_map(Result::ok)
-        .filter(|line| !line.trim().is_empty())
-        .collect();
+// Risposta NDJSON in due righe: prima i match esatti (distanza 0), poi quelli
+// fuzzy calcolati in background mentre la prima riga è già in viaggio
+fn stream_search(
+    search: Search,
+    dataset_data: Data<RwLock<Dataset>>,
+    config: Data<Config>,
+    pools: Data<ThreadPools>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let start_time = Instant::now();
+    let slow_query_ms = config.slow_query_ms;
+    let seeded = search.seed.is_some();
+    let dataset = dataset_data.read().expect("dataset lock poisoned");
+    let mut exact = pools.install(search.parallelism, || search.exact_matches(&dataset));
+    exact.truncate(search.limit);
+    let suggestion = search
+        .params
+        .suggest
+        .then(|| search.suggestion(&dataset))
+        .flatten();
+    let reserved = reserved_size(None, suggestion.as_deref(), search.warning.as_deref());
+
+    let exact_truncated = match apply_size_guard(
+        &mut exact,
+        config.max_response_bytes,
+        config.oversize_policy,
+        reserved,
+        false,
+    ) {
+        Ok(truncated) => truncated,
+        Err(size) => return payload_too_large(size, &config),
+    };
+    // se la prima riga è già stata tagliata non c'è spazio per la seconda
+    let remaining = if exact_truncated {
+        0
+    } else {
+        search.limit - exact.len()
+    };
+    let remaining_bytes = config.max_response_bytes.saturating_sub(
+        reserved
+            + exact
+                .iter()
+                .map(SearchResult::estimated_size)
+                .sum::<usize>(),
+    );
+
+    let exact_line = ndjson_line(&StreamChunk {
+        stage: "exact",
+        results: exact,
+        response_time: (!seeded).then(|| start_time.elapsed().as_millis() as u64),
+        version: dataset.version,
+        truncated: exact_truncated,
+        suggestion,
+        arm: search.arm,
+        canary_fallback: search.canary_fallback.clone(),
+        rejected: search.rejected,
+        warning: search.warning.clone(),
+        autocorrect: None,
+    });
+    drop(dataset);
+
+    let fuzzy = spawn_blocking(move || {
+        let dataset = dataset_data.read().expect("dataset lock poisoned");
+        if remaining == 0 {
+            return (Vec::new(), dataset.version, false, None);
+        }
+        let mut results =
+            pools.install(search.parallelism, || search.fuzzy_matches(&dataset, true));
+        results.sort_by_key(|item| item.distance);
+        // con un match esatto non c'è niente da correggere
+        let autocorrect = (search.params.autocorrect && remaining == search.limit)
+            .then(|| search.autocorrect(&results))
+            .flatten();
+        results.truncate(remaining);
+        // lo status è già partito con la prima riga: qui si può solo troncare
+        let truncated = match apply_size_guard(
+            &mut results,
+            remaining_bytes,
+            OversizePolicy::Truncate,
+            0,
+            false,
+        ) {
+            Ok(truncated) => truncated,
+            Err(_) => {
+                results.clear();
+                true
+            }
+        };
+        (results, dataset.version, truncated, autocorrect)
+    });
+
+    let fuzzy_line = async move {
+        // la risposta è già partita: il middleware non vede né errori né
+        // durata della seconda riga, quindi si registrano qui
+        let RequestId(id) = request_id;
+        let (results, version, truncated, autocorrect) = fuzzy.await.map_err(|error| {
+            eprintln!("[{id}] fuzzy stage failed: {error}");
+            ErrorInternalServerError(error)
+        })?;
+        let elapsed = start_time.elapsed().as_millis() as u64;
+        if elapsed >= slow_query_ms {
+            eprintln!("[{id}] slow fuzzy stage: {elapsed} ms");
+        }
+        Ok::<_, Error>(ndjson_line(&StreamChunk {
+            stage: "fuzzy",
+            results,
+            response_time: (!seeded).then(|| start_time.elapsed().as_millis() as u64),
+            version,
+            truncated,
+            suggestion: None,
+            arm: None,
+            canary_fallback: None,
+            rejected: false,
+            warning: None,
+            autocorrect,
+        }))
+    };
+
+    let body = stream::once(ready(Ok::<_, Error>(exact_line))).chain(stream::once(fuzzy_line));
+
+    HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(body)
+}
+
+fn ndjson_line<T: Serialize>(value: &T) -> Bytes {
+    let mut line = serde_json::to_vec(value).unwrap_or_default();
+    line.push(b'\n');
+    Bytes::from(line)
+}
 
-    Ok(names)
+#[derive(Deserialize)]
+struct AppendParams {
+    lines: Vec<String>,
 }
 
-#[inline(always)]
-fn calc_dist_bytes(a: &[u8], b: &[u8]) -> usize {
-    let len_a = a.len();
-    let len_b = b.len();
+#[derive(Serialize)]
+struct AppendResponse {
+    added: usize,
+    version: u64,
+}
 
-    if len_a == 0 {
-        return len_b;
+async fn post_names(
+    request: HttpRequest,
+    params: Json<AppendParams>,
+    dataset_data: Data<RwLock<Dataset>>,
+    config: Data<Config>,
+    progress: Data<LoadProgress>,
+) -> HttpResponse {
+    if !config.allow_append {
+        return HttpResponse::NotFound().json(ErrorResponse {
+            error: "appending names is disabled (FUZZY_ALLOW_APPEND is not set)".to_string(),
+        });
+    }
+    if !is_admin(&request, &config) {
+        return HttpResponse::Unauthorized().json(ErrorResponse {
+            error: "POST /names needs the admin token".to_string(),
+        });
+    }
+    let params = params.into_inner();
+    if params.lines.len() > config.max_append_lines {
+        return HttpResponse::PayloadTooLarge().json(ErrorResponse {
+            error: format!(
+                "{} lines in one request, over the FUZZY_MAX_APPEND_LINES limit of {}",
+                params.lines.len(),
+                config.max_append_lines
+            ),
+        });
     }
-    if len_b == 0 {
-        return len_a;
+    // il dataset caricato sostituirà quello vuoto e le aggiunte andrebbero perse
+    if !progress.is_loaded() {
+        return still_loading(&progress);
     }
 
-    let mut prev: Vec<usize> = (0..=len_b).collect();
-    let mut curr = vec![0; len_b + 1];
+    let mut dataset = dataset_data.write().expect("dataset lock poisoned");
+    let added = dataset.append(params.lines);
 
-    for i in 0..len_a {
-        curr[0] = i + 1;
+    HttpResponse::Ok().json(AppendResponse {
+        added,
+        version: dataset.version,
+    })
+}
 
-        for j in 0..len_b {
-            let cost = if a[i] == b[j] { 0 } else { 1 };
+#[derive(Serialize)]
+struct StatsResponse {
+    records: usize,
+    version: u64,
+    // tutta la memoria dei record: il vettore con le struct stesse, più
+    // stringhe, campi, alias e forme traslitterate di ciascuno
+    records_bytes: usize,
+    vocabulary_tokens: usize,
+    vocabulary_bytes: usize,
+    total_bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warm_up_ms: Option<u64>,
+    memory_locked: bool,
+}
 
-            curr[j + 1] = (prev[j + 1] + 1).min(curr[j] + 1).min(prev[j] + cost);
-        }
+async fn get_stats(
+    dataset_data: Data<RwLock<Dataset>>,
+    warm_up_report: Data<OnceLock<WarmUpReport>>,
+) -> HttpResponse {
+    let dataset = dataset_data.read().expect("dataset lock poisoned");
+    let records_bytes = dataset.records.heap_size();
+    let vocabulary_bytes = dataset.vocabulary.heap_size();
 
-        std::mem::swap(&mut prev, &mut curr);
+    HttpResponse::Ok().json(StatsResponse {
+        records: dataset.records.len(),
+        version: dataset.version,
+        records_bytes,
+        vocabulary_tokens: dataset.vocabulary.len(),
+        vocabulary_bytes,
+        total_bytes: records_bytes + vocabulary_bytes,
+        warm_up_ms: warm_up_report.get().map(|report| report.duration_ms),
+        memory_locked: warm_up_report
+            .get()
+            .is_some_and(|report| report.memory_locked),
+    })
+}
+
+#[derive(Serialize)]
+struct ReadyResponse {
+    ready: bool,
+    // percentuale del file già letta
+    progress: f64,
+}
+
+// 200 quando il dataset è caricato, 503 con l'avanzamento finché non lo è
+async fn get_ready(progress: Data<LoadProgress>) -> HttpResponse {
+    let response = ReadyResponse {
+        ready: progress.is_loaded(),
+        progress: (progress.percent() * 10.0).round() / 10.0,
+    };
+
+    if response.ready {
+        HttpResponse::Ok().json(response)
+    } else {
+        HttpResponse::ServiceUnavailable().json(response)
     }
+}
 
-    prev[len_b]
+#[derive(Deserialize)]
+struct SuggestParams {
+    #[serde(default)]
+    prefix: String,
+    limit: Option<usize>,
 }
 
-fn fuzzy_match(query_bytes: &[u8], full_name: &str) -> usize {
-    if !full_name.contains(' ') {
-        return calc_dist_bytes(query_bytes, full_name.as_bytes());
+#[derive(Serialize)]
+struct SuggestResponse {
+    suggestions: Vec<Suggestion>,
+}
+
+// Query passate più frequenti che iniziano con `prefix`, per il menu a tendina
+async fn get_suggest(params: Query<SuggestParams>, history: Data<SearchHistory>) -> HttpResponse {
+    if !history.is_enabled() {
+        return HttpResponse::NotFound().json(ErrorResponse {
+            error: "search history is disabled (FUZZY_HISTORY_SIZE=0)".to_string(),
+        });
     }
 
-    full_name
-        .split_whitespace()
-        .map(|part| calc_dist_bytes(query_bytes, part.as_bytes()))
-        .min()
-        .unwrap_or(usize::MAX)
+    HttpResponse::Ok().json(SuggestResponse {
+        suggestions: history.suggest(
+            &params.prefix,
+            params.limit.unwrap_or(search::DEFAULT_LIMIT),
+        ),
+    })
+}
+
+// Valori usati quando la richiesta non specifica il parametro
+#[derive(Serialize)]
+struct SearchDefaults {
+    algorithm: distance::Algorithm,
+    limit: usize,
+    max_distance: usize,
+    min_score: f64,
+    ngram_size: usize,
+}
+
+#[derive(Serialize)]
+struct ConfigResponse<'a> {
+    #[serde(flatten)]
+    config: &'a Config,
+    defaults: SearchDefaults,
+    // niente indice di ricerca né cache dei risultati: ogni query scandisce tutto
+    index: &'static str,
+    cache_size: usize,
+}
+
+// Configurazione effettiva, già risolta dalle variabili d'ambiente; con
+// FUZZY_ADMIN_TOKEN serve `Authorization: Bearer <token>`
+async fn get_config(request: HttpRequest, config: Data<Config>) -> HttpResponse {
+    if !is_admin(&request, &config) {
+        return HttpResponse::Unauthorized().json(ErrorResponse {
+            error: "GET /config needs the admin token".to_string(),
+        });
+    }
+
+    HttpResponse::Ok().json(ConfigResponse {
+        config: &config,
+        defaults: SearchDefaults {
+            algorithm: distance::Algorithm::default(),
+            limit: search::DEFAULT_LIMIT,
+            max_distance: search::DEFAULT_MAX_DISTANCE,
+            min_score: search::DEFAULT_MIN_SCORE,
+            ngram_size: distance::DEFAULT_NGRAM_SIZE,
+        },
+        index: "none",
+        cache_size: 0,
+    })
+}
+
+// Senza FUZZY_ADMIN_TOKEN ogni richiesta lo è
+fn is_admin(request: &HttpRequest, config: &Config) -> bool {
+    let Some(token) = &config.admin_token else {
+        return true;
+    };
+    request
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes()))
+}
+
+// Confronto che non si ferma al primo byte diverso, per non rivelare il token
+// con i tempi di risposta
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn still_loading(progress: &LoadProgress) -> HttpResponse {
+    HttpResponse::ServiceUnavailable().json(ErrorResponse {
+        error: format!(
+            "the dataset is still loading ({:.1}%), see /ready",
+            progress.percent()
+        ),
+    })
 }
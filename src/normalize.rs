@@ -0,0 +1,67 @@
+use std::{borrow::Cow, collections::HashSet};
+
+use regex::Regex;
+
+// Numero o luogo tra parentesi, oppure tutto quello che segue l'ultima virgola:
+// "John Smith (1975)", "Paris, TX"
+pub const DEFAULT_SUFFIX_PATTERN: &str = r"\([^()]*\)|,[^,]*";
+
+// Percorso veloce per l'ASCII (nessuna allocazione se è già minuscolo),
+// folding Unicode completo solo quando serve
+pub fn fold_case(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        if text.bytes().any(|byte| byte.is_ascii_uppercase()) {
+            Cow::Owned(text.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(text)
+        }
+    } else {
+        Cow::Owned(text.to_lowercase())
+    }
+}
+
+// Trasformazioni applicate al nome al caricamento; il nome originale resta
+// quello mostrato nei risultati
+#[derive(Clone, Default)]
+pub struct Normalizer {
+    pub dedup_tokens: bool,
+    // già ancorata alla fine del nome
+    pub strip_suffix: Option<Regex>,
+}
+
+impl Normalizer {
+    // Compila il pattern del suffisso da togliere, ancorandolo alla fine
+    pub fn suffix_regex(pattern: &str) -> Result<Regex, String> {
+        Regex::new(&format!(r"\s*(?:{pattern})\s*$"))
+            .map_err(|error| format!("invalid FUZZY_STRIP_SUFFIX_PATTERN: {error}"))
+    }
+
+    // `None` se la forma usata per il matching coincide con il nome
+    pub fn apply(&self, name: &str) -> Option<String> {
+        let stripped = match &self.strip_suffix {
+            // un nome fatto solo di suffisso resta com'è
+            Some(regex) => match regex.find(name) {
+                Some(found) if found.start() > 0 => &name[..found.start()],
+                _ => name,
+            },
+            None => name,
+        };
+        let normalized = if self.dedup_tokens {
+            Cow::Owned(dedup_tokens(stripped))
+        } else {
+            Cow::Borrowed(stripped)
+        };
+
+        (normalized != name).then(|| normalized.into_owned())
+    }
+}
+
+// "John john Smith" -> "John Smith": resta la prima occorrenza di ogni token,
+// confrontando i token in minuscolo
+pub fn dedup_tokens(text: &str) -> String {
+    let mut seen = HashSet::new();
+    text.split_whitespace()
+        .filter(|token| seen.insert(fold_case(token).into_owned()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,36 @@
+// Codice Soundex di 4 cifre, con la differenza che anche la prima lettera è
+// codificata invece di restare com'è: così "Katharyn" e "Catherine" hanno lo
+// stesso codice (2365). Vocali e `y` separano due consonanti con la stessa
+// cifra, `h` e `w` no; i caratteri non ASCII sono ignorati. `None` se il testo
+// non ha consonanti codificabili.
+pub fn soundex(text: &str) -> Option<[u8; 4]> {
+    let mut code = [b'0'; 4];
+    let mut len = 0;
+    let mut last = 0;
+
+    for byte in text.bytes() {
+        let digit = match byte.to_ascii_lowercase() {
+            b'b' | b'f' | b'p' | b'v' => b'1',
+            b'c' | b'g' | b'j' | b'k' | b'q' | b's' | b'x' | b'z' => b'2',
+            b'd' | b't' => b'3',
+            b'l' => b'4',
+            b'm' | b'n' => b'5',
+            b'r' => b'6',
+            b'a' | b'e' | b'i' | b'o' | b'u' | b'y' => {
+                last = 0;
+                continue;
+            }
+            _ => continue,
+        };
+        if digit != last {
+            code[len] = digit;
+            len += 1;
+            if len == code.len() {
+                break;
+            }
+        }
+        last = digit;
+    }
+
+    (len > 0).then_some(code)
+}
@@ -0,0 +1,45 @@
+use std::sync::{Arc, Mutex};
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+// Dimensioni tenute in vita insieme; la meno usata di recente viene chiusa
+const MAX_CACHED_POOLS: usize = 4;
+
+// Un pool rayon per dimensione richiesta (una potenza di due o il tetto, vedi
+// `Search::new`), creato alla prima richiesta e poi riusato: costruirlo a ogni
+// ricerca costerebbe più della scansione. I pool aperti sono al più
+// MAX_CACHED_POOLS, dal più recente al meno recente.
+#[derive(Default)]
+pub struct ThreadPools {
+    pools: Mutex<Vec<(usize, Arc<ThreadPool>)>>,
+}
+
+impl ThreadPools {
+    // Senza dimensione si usa il pool globale
+    pub fn install<R: Send>(&self, size: Option<usize>, work: impl FnOnce() -> R + Send) -> R {
+        let Some(size) = size else {
+            return work();
+        };
+
+        let pool = {
+            let mut pools = self.pools.lock().expect("thread pools lock poisoned");
+            let entry = match pools.iter().position(|(cached, _)| *cached == size) {
+                Some(position) => pools.remove(position),
+                None => {
+                    // un pool chiuso mentre lo usa una ricerca vive fino alla sua fine
+                    pools.truncate(MAX_CACHED_POOLS - 1);
+                    let pool = ThreadPoolBuilder::new()
+                        .num_threads(size)
+                        .build()
+                        .expect("failed to build thread pool");
+                    (size, Arc::new(pool))
+                }
+            };
+            let pool = entry.1.clone();
+            pools.insert(0, entry);
+            pool
+        };
+
+        pool.install(work)
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::normalize::fold_case;
+
+// Limiti dell'albero: oltre, i nomi restano foglie del nodo raggiunto
+pub const MAX_TREE_DEPTH: usize = 8;
+pub const MAX_TREE_NODES: usize = 64;
+
+// Nodo di un albero radix sui nomi in minuscolo: `prefix` è il prefisso
+// completo dalla radice, `results` gli indici nella lista dei risultati dei
+// nomi che finiscono qui (o che non si è potuto dividere oltre)
+#[derive(Serialize)]
+pub struct PrefixNode {
+    pub prefix: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub results: Vec<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<PrefixNode>,
+}
+
+pub fn prefix_tree<'a>(names: impl IntoIterator<Item = &'a str>) -> PrefixNode {
+    let items = names
+        .into_iter()
+        .map(|name| fold_case(name).chars().collect())
+        .enumerate()
+        .collect::<Vec<_>>();
+    if items.is_empty() {
+        return PrefixNode {
+            prefix: String::new(),
+            results: Vec::new(),
+            children: Vec::new(),
+        };
+    }
+    let mut nodes = 1;
+    build(items, 0, 0, &mut nodes)
+}
+
+// `items` condividono già i primi `start` caratteri
+fn build(
+    items: Vec<(usize, Vec<char>)>,
+    start: usize,
+    depth: usize,
+    nodes: &mut usize,
+) -> PrefixNode {
+    let first = &items[0].1;
+    let mut end = first.len();
+    for (_, chars) in &items[1..] {
+        end = start
+            + first[start..end]
+                .iter()
+                .zip(&chars[start..])
+                .take_while(|(a, b)| a == b)
+                .count();
+    }
+    let prefix = first[..end].iter().collect();
+
+    let mut results = Vec::new();
+    let mut groups: BTreeMap<char, Vec<(usize, Vec<char>)>> = BTreeMap::new();
+    for (index, chars) in items {
+        match chars.get(end) {
+            Some(&next) => groups.entry(next).or_default().push((index, chars)),
+            None => results.push(index),
+        }
+    }
+
+    // senza spazio per tutti i figli, il nodo tiene i nomi come foglie
+    if depth == MAX_TREE_DEPTH || *nodes + groups.len() > MAX_TREE_NODES {
+        results.extend(groups.into_values().flatten().map(|(index, _)| index));
+        results.sort_unstable();
+        return PrefixNode {
+            prefix,
+            results,
+            children: Vec::new(),
+        };
+    }
+
+    *nodes += groups.len();
+    let children = groups
+        .into_values()
+        .map(|group| build(group, end, depth + 1, nodes))
+        .collect();
+    PrefixNode {
+        prefix,
+        results,
+        children,
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Instant,
+};
+
+// Avanzamento del caricamento, condiviso tra il thread che legge il file e
+// `/ready`; con `every` > 0 logga ogni `every` righe
+pub struct LoadProgress {
+    every: u64,
+    start_time: Instant,
+    total_bytes: AtomicU64,
+    bytes_read: AtomicU64,
+    lines: AtomicU64,
+    loaded: AtomicBool,
+}
+
+impl LoadProgress {
+    pub fn new(every: u64) -> LoadProgress {
+        LoadProgress {
+            every,
+            start_time: Instant::now(),
+            total_bytes: AtomicU64::new(0),
+            bytes_read: AtomicU64::new(0),
+            lines: AtomicU64::new(0),
+            loaded: AtomicBool::new(false),
+        }
+    }
+
+    pub fn start(&self, total_bytes: u64) {
+        self.total_bytes.store(total_bytes, Ordering::Relaxed);
+    }
+
+    // Una riga letta, con il suo terminatore
+    pub fn line(&self, bytes: u64) {
+        self.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+        let lines = self.lines.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if self.every > 0 && lines.is_multiple_of(self.every) {
+            println!(
+                "Loading: {lines} lines read ({:.1}%) in {} ms",
+                self.percent(),
+                self.start_time.elapsed().as_millis()
+            );
+        }
+    }
+
+    pub fn finish(&self) {
+        self.loaded.store(true, Ordering::Release);
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.load(Ordering::Acquire)
+    }
+
+    // Percentuale dei byte letti; le righe CRLF contano un byte in meno,
+    // quindi si limita a 100 e si arriva a 100 solo a caricamento finito
+    pub fn percent(&self) -> f64 {
+        if self.is_loaded() {
+            return 100.0;
+        }
+        let total = self.total_bytes.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0.0;
+        }
+        let read = self.bytes_read.load(Ordering::Relaxed);
+        (read as f64 * 100.0 / total as f64).min(99.9)
+    }
+}
@@ -0,0 +1,66 @@
+use std::{future::Future, time::Instant};
+
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    Error, HttpMessage,
+};
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+// Id della richiesta, disponibile agli handler via `ReqData<RequestId>`
+#[derive(Clone)]
+pub struct RequestId(pub String);
+
+// Middleware per `wrap_fn`: riusa l'X-Request-Id del client se sensato o ne
+// genera uno, lo rimanda nella risposta e lo mette nei log di errori e
+// richieste lente
+pub fn propagate<S, B>(
+    request: ServiceRequest,
+    service: &S,
+    slow_query_ms: u64,
+) -> impl Future<Output = Result<ServiceResponse<B>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid(value))
+        .map_or_else(generate, str::to_string);
+    request.extensions_mut().insert(RequestId(id.clone()));
+
+    let start_time = Instant::now();
+    let method = request.method().clone();
+    let path = request.path().to_string();
+    let response = service.call(request);
+
+    async move {
+        let mut response = response.await?;
+        let elapsed = start_time.elapsed().as_millis() as u64;
+        let status = response.status();
+
+        if status.is_server_error() || status.is_client_error() {
+            eprintln!("[{id}] {method} {path} -> {status} in {elapsed} ms");
+        } else if elapsed >= slow_query_ms {
+            eprintln!("[{id}] slow request: {method} {path} -> {status} in {elapsed} ms");
+        }
+
+        if let Ok(value) = HeaderValue::from_str(&id) {
+            response.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+        Ok(response)
+    }
+}
+
+// Id del client accettati così come sono: ASCII stampabile, niente spazi
+fn is_valid(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 128 && id.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+fn generate() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
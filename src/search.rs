@@ -0,0 +1,1404 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::{
+    config::{Config, EmptyQuery, LowVarietyPolicy, MissingTimestamp},
+    cost_matrix::CostMatrix,
+    cursor::Cursor,
+    dataset::{fnv1a, Dataset, Record},
+    distance::{
+        calc_dist_bytes, calc_dist_word_boundaries, dp_cells, missing_ngrams, ngrams,
+        subsequence_window, Algorithm, EditOps, DEFAULT_NGRAM_SIZE,
+    },
+    graphemes::graphemes,
+    join::JoinTable,
+    normalize::fold_case,
+    phonetic::soundex,
+    timestamp::parse_timestamp,
+};
+
+pub const DEFAULT_LIMIT: usize = 10;
+pub const DEFAULT_MAX_DISTANCE: usize = 2;
+// oltre, `recency_days` in secondi non sta in un i64
+const MAX_RECENCY_DAYS: u64 = (i64::MAX / 86_400) as u64;
+// il più grande intero che un double (JSON in JavaScript) rappresenta esatto
+pub const MAX_SEED: u64 = (1 << 53) - 1;
+// `ngram_containment` senza `min_score` né `max_distance`
+pub const DEFAULT_MIN_SCORE: f64 = 0.5;
+pub const DEFAULT_SCORE_PRECISION: u32 = 3;
+pub const MAX_SCORE_PRECISION: u32 = 15;
+// `diversity`: il riordino considera solo i primi DIVERSITY_POOL match, così il
+// costo (una distanza per scelta e candidato) non cresce con `limit`
+pub const DIVERSITY_POOL: usize = 100;
+pub const MAX_DIVERSITY_LIMIT: usize = 50;
+// `rank_confidence`: temperatura della softmax sugli score
+const RANK_CONFIDENCE_TEMPERATURE: f64 = 0.1;
+// `autocorrect`: distanza massima del primo match e vantaggio minimo sul
+// secondo testo distinto
+const AUTOCORRECT_MAX_DISTANCE: usize = 1;
+const AUTOCORRECT_MARGIN: usize = 1;
+
+#[derive(Deserialize, Clone)]
+pub struct SearchParams {
+    pub query: String,
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(default)]
+    pub score_base: ScoreBase,
+    #[serde(default)]
+    pub ignore_case: bool,
+    // quello chiesto dal client; `algorithm` è quello risolto da `Search::new`
+    #[serde(rename = "algorithm")]
+    pub requested_algorithm: Option<Algorithm>,
+    #[serde(skip)]
+    pub algorithm: Algorithm,
+    pub concat_fields: Option<Vec<String>>,
+    pub concat_separator: Option<String>,
+    pub since_version: Option<u64>,
+    #[serde(default)]
+    pub require_first_char: bool,
+    #[serde(default)]
+    pub score_scale: ScoreScale,
+    pub parallelism: Option<usize>,
+    pub limit: Option<usize>,
+    pub max_distance: Option<usize>,
+    // solo per `ngram_containment`: frazione minima di n-grammi della query presenti
+    pub min_score: Option<f64>,
+    pub ngram_size: Option<usize>,
+    #[serde(default)]
+    pub match_mode: MatchMode,
+    #[serde(default)]
+    pub word_boundaries: bool,
+    #[serde(default)]
+    pub suggest: bool,
+    // solo per `subsequence`: caratteri della query / lunghezza della finestra
+    pub min_density: Option<f64>,
+    #[serde(default)]
+    pub op_counts: bool,
+    #[serde(default)]
+    pub line_numbers: bool,
+    #[serde(default)]
+    pub autocorrect: bool,
+    #[serde(default)]
+    pub graphemes: bool,
+    // `next_cursor` della pagina precedente
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub sample: bool,
+    pub seed: Option<u64>,
+    // solo record con timestamp da questo istante in poi (stessi formati della colonna)
+    pub since: Option<String>,
+    pub recency_days: Option<u64>,
+    // peso della diversità nel riordino MMR, in [0, 1]
+    pub diversity: Option<f64>,
+    #[serde(default)]
+    pub rank_confidence: bool,
+    // campo per cui contare i match, su tutti quelli entro la soglia
+    pub facet_by: Option<String>,
+    pub group_by: Option<GroupBy>,
+    // restituisce `dp_cells`, le celle DP calcolate per la richiesta
+    #[serde(default)]
+    pub profile: bool,
+    // scarta i token la cui lunghezza differisce dalla query più di `max_distance`
+    #[serde(default)]
+    pub length_filter: bool,
+    // niente scansione fuzzy se i match esatti bastano a riempire `limit`
+    #[serde(default)]
+    pub early_abort: bool,
+    // aggiunge ai risultati gli attributi del file FUZZY_JOIN_PATH
+    #[serde(default)]
+    pub enrich: bool,
+    // braccio imposto dall'header X-Canary, non dal corpo JSON
+    #[serde(skip)]
+    pub forced_arm: Option<Arm>,
+}
+
+// Componenti che estraggono numeri casuali, ciascuna con il suo flusso del
+// generatore
+#[derive(Clone, Copy)]
+enum RngStream {
+    Sample,
+}
+
+// Braccio dell'esperimento canary: l'algoritmo di default o quello sperimentale
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Arm {
+    Control,
+    Canary,
+}
+
+// Raggruppamento dei risultati restituiti, accanto alla lista piatta
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    PrefixTree,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    // distanza minima tra la query e i singoli token del nome
+    #[default]
+    Tokens,
+    // distanza tra la query e il nome intero
+    WholeString,
+    // la migliore tra le due: il nome intero e il token più vicino
+    Combined,
+}
+
+// Denominatore usato per normalizzare la distanza nello score [0, 1]
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ScoreBase {
+    #[default]
+    Max,
+    Query,
+    Matched,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ScoreScale {
+    #[default]
+    Float,
+    Int100,
+}
+
+// Serializzato come `"score": 0.93` oppure `"score_int": 93`; il valore
+// interno resta sempre il float usato per ordinare
+#[derive(Serialize, Debug, Clone, Copy)]
+pub enum Score {
+    #[serde(rename = "score", serialize_with = "serialize_rounded")]
+    Float(f64),
+    #[serde(rename = "score_int", serialize_with = "serialize_int100")]
+    Int100(f64),
+}
+
+impl Score {
+    fn new(scale: ScoreScale, value: f64) -> Score {
+        match scale {
+            ScoreScale::Float => Score::Float(value),
+            ScoreScale::Int100 => Score::Int100(value),
+        }
+    }
+
+    fn value(self) -> f64 {
+        match self {
+            Score::Float(value) | Score::Int100(value) => value,
+        }
+    }
+}
+
+// Hash della query e dei parametri che cambiano quali risultati escono o il
+// loro ordine (l'algoritmo è quello risolto, canary compreso): un cursore vale
+// solo per la stessa combinazione, altrimenti le pagine avrebbero buchi o
+// doppioni. Presentazione (score, campi extra) e ottimizzazioni non contano.
+fn params_hash(params: &SearchParams, query: &str) -> u64 {
+    let key = format!(
+        "{query}\0{}\0{:?}\0{:?}\0{:?}\0{}\0{}\0{:?}\0{:?}\0{}\0{}\0{:?}\0{}\0{:?}\0{:?}\0{:?}",
+        params.algorithm as u8,
+        params.max_distance,
+        params.min_score,
+        params.ngram_size,
+        params.ignore_case,
+        params.match_mode as u8,
+        params.concat_fields,
+        params.concat_separator,
+        params.require_first_char,
+        params.word_boundaries,
+        params.min_density,
+        params.graphemes,
+        params.since_version,
+        params.since,
+        params.recency_days,
+    );
+    fnv1a(key.as_bytes())
+}
+
+// Arrotondamento half-up; l'epsilon evita che 0.285 * 100 = 28.4999...
+// finisca nel verso sbagliato
+fn to_int100(score: f64) -> u8 {
+    (score * 100.0 + 0.5 + 1e-9).floor().clamp(0.0, 100.0) as u8
+}
+
+// Cifre decimali di `score` nelle risposte (FUZZY_SCORE_PRECISION); i calcoli
+// e gli ordinamenti usano sempre il valore completo
+static SCORE_PRECISION: AtomicU32 = AtomicU32::new(DEFAULT_SCORE_PRECISION);
+
+pub fn set_score_precision(digits: u32) {
+    SCORE_PRECISION.store(digits, Ordering::Relaxed);
+}
+
+fn serialize_rounded<S: Serializer>(score: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    let scale = 10f64.powi(SCORE_PRECISION.load(Ordering::Relaxed) as i32);
+    serializer.serialize_f64((score * scale).round() / scale)
+}
+
+// Per gli altri valori in [0, 1] delle risposte, come `rank_confidence`
+pub fn serialize_rounded_option<S: Serializer>(
+    value: &Option<f64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serialize_rounded(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn serialize_int100<S: Serializer>(score: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u8(to_int100(*score))
+}
+
+#[derive(Serialize, Debug)]
+pub struct SearchResult {
+    pub name: String,
+    pub distance: usize,
+    #[serde(flatten)]
+    pub score: Score,
+    pub row_hash: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+    // alias che ha dato il match, se migliore del nome canonico
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched_alias: Option<String>,
+    // il match viene dalla forma traslitterata del nome
+    #[serde(skip_serializing_if = "crate::is_false")]
+    pub transliterated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub op_counts: Option<EditOps>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_number: Option<usize>,
+    // solo con `enrich`: un attributo assente nel file vale `null`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<BTreeMap<String, serde_json::Value>>,
+    // token (o testo intero) che ha dato la distanza, solo per `autocorrect`
+    #[serde(skip)]
+    pub matched_text: Option<String>,
+    // posizione nel dataset, seconda chiave di ordinamento per i cursori
+    #[serde(skip)]
+    pub index: usize,
+}
+
+impl SearchResult {
+    // Stima della dimensione serializzata senza serializzare: ~100 byte fissi
+    // per chiavi, virgolette e numeri, più i testi
+    pub fn estimated_size(&self) -> usize {
+        100 + self.name.len()
+            + self.row_hash.len()
+            + self
+                .fields
+                .iter()
+                .map(|(key, value)| key.len() + value.len() + 6)
+                .sum::<usize>()
+            + self
+                .matched_alias
+                .as_ref()
+                .map_or(0, |alias| alias.len() + 18)
+            + if self.transliterated { 22 } else { 0 }
+            + self.op_counts.map_or(0, |_| 90)
+            + self.line_number.map_or(0, |_| 36)
+            + self.attributes.as_ref().map_or(0, |attributes| {
+                16 + attributes
+                    .iter()
+                    .map(|(key, value)| key.len() + value.to_string().len() + 4)
+                    .sum::<usize>()
+            })
+    }
+}
+
+// Cosa viene confrontato con la query per ogni record
+enum Target {
+    Name,
+    Concat {
+        fields: Vec<usize>,
+        separator: String,
+    },
+}
+
+// Richiesta validata, con la query già normalizzata
+pub struct Search {
+    pub params: SearchParams,
+    query: String,
+    target: Target,
+    // primo carattere della query normalizzata, se `require_first_char`
+    first_char: Option<char>,
+    empty_query: EmptyQuery,
+    frequency_column: Option<usize>,
+    // thread effettivi per la scansione, già limitati dal tetto del server
+    pub parallelism: Option<usize>,
+    pub limit: usize,
+    max_distance: usize,
+    // solo per l'algoritmo `weighted`
+    cost_matrix: Option<Arc<CostMatrix>>,
+    // chiave (distanza, indice) dell'ultimo risultato della pagina precedente
+    after: Option<(usize, usize)>,
+    // vedi `params_hash`, scritto nei cursori
+    params_hash: u64,
+    // da `since` o `recency_days`, in secondi Unix
+    not_before: Option<i64>,
+    missing_timestamp: MissingTimestamp,
+    // solo con un canary configurato e senza `algorithm` nella richiesta
+    pub arm: Option<Arm>,
+    // perché il canary non poteva servire la richiesta, passata al controllo
+    pub canary_fallback: Option<String>,
+    facet_column: Option<usize>,
+    // tabella e colonna della chiave (`None`: il nome), solo con `enrich`
+    join: Option<(Arc<JoinTable>, Option<usize>)>,
+    // n-grammi distinti della query, solo per `ngram_containment`
+    query_ngrams: Option<Vec<String>>,
+    // Soundex della query, solo per `phonetic_fuzzy`
+    query_soundex: Option<[u8; 4]>,
+    // somma dei contatori per thread, solo con `profile`
+    dp_cells: AtomicU64,
+    // seme di tutte le componenti casuali: quello della richiesta, oppure uno
+    // estratto a caso se la richiesta ne usa qualcuna
+    pub seed: Option<u64>,
+    // motivo per cui la query è poco selettiva, se lo è
+    pub warning: Option<String>,
+    // con `LowVarietyPolicy::Reject`: nessun match, solo `warning`
+    pub rejected: bool,
+}
+
+impl Search {
+    pub fn new(
+        mut params: SearchParams,
+        dataset: &Dataset,
+        config: &Config,
+    ) -> Result<Search, String> {
+        // un algoritmo esplicito vince sempre; altrimenti, se c'è un canary,
+        // decide l'header o l'hash della query
+        let arm = config.canary.as_ref().map(|canary| {
+            params.forced_arm.unwrap_or_else(|| {
+                let bucket = fnv1a(fold_case(params.query.trim()).as_bytes()) % 100;
+                if bucket < canary.percent as u64 {
+                    Arm::Canary
+                } else {
+                    Arm::Control
+                }
+            })
+        });
+        params.algorithm = match (params.requested_algorithm, arm, &config.canary) {
+            (Some(algorithm), _, _) => algorithm,
+            (None, Some(Arm::Canary), Some(canary)) => canary.algorithm,
+            _ => Algorithm::default(),
+        };
+        let arm = arm.filter(|_| params.requested_algorithm.is_none());
+
+        // se l'algoritmo del canary non accetta i parametri della richiesta si
+        // torna al controllo: chi cade nel secchio non deve vedere un 400
+        if arm == Some(Arm::Canary) {
+            let canary = params.clone();
+            params.algorithm = Algorithm::default();
+            return match Search::build(canary, dataset, config, arm) {
+                Ok(search) => Ok(search),
+                Err(error) => Ok(Search {
+                    canary_fallback: Some(error),
+                    ..Search::build(params, dataset, config, Some(Arm::Control))?
+                }),
+            };
+        }
+        Search::build(params, dataset, config, arm)
+    }
+
+    // Validazione e preparazione, con l'algoritmo già deciso
+    fn build(
+        params: SearchParams,
+        dataset: &Dataset,
+        config: &Config,
+        arm: Option<Arm>,
+    ) -> Result<Search, String> {
+        let query = if params.ignore_case {
+            fold_case(&params.query).into_owned()
+        } else {
+            params.query.clone()
+        };
+
+        let target = match &params.concat_fields {
+            None => Target::Name,
+            Some(names) if names.is_empty() => {
+                return Err("concat_fields must not be empty".to_string());
+            }
+            Some(names) => Target::Concat {
+                fields: names
+                    .iter()
+                    .map(|name| {
+                        dataset
+                            .column(name)
+                            .ok_or_else(|| format!("unknown field `{name}`"))
+                    })
+                    .collect::<Result<_, _>>()?,
+                separator: params
+                    .concat_separator
+                    .clone()
+                    .unwrap_or_else(|| " ".to_string()),
+            },
+        };
+
+        let parallelism = match params.parallelism {
+            Some(0) => return Err("parallelism must be at least 1".to_string()),
+            // arrotondato in su alla potenza di due, così i pool di `ThreadPools`
+            // sono pochi, ma mai oltre il tetto del server
+            hint => hint.map(|threads| threads.next_power_of_two().min(config.max_parallelism)),
+        };
+
+        let limit = match params.limit {
+            Some(0) => return Err("limit must be at least 1".to_string()),
+            limit => limit.unwrap_or(DEFAULT_LIMIT),
+        };
+        let mut max_distance = params.max_distance.unwrap_or(DEFAULT_MAX_DISTANCE);
+
+        if let Some(min_density) = params.min_density {
+            if params.algorithm != Algorithm::Subsequence {
+                return Err("min_density is only supported by subsequence".to_string());
+            }
+            if !(min_density > 0.0 && min_density <= 1.0) {
+                return Err("min_density must be in (0, 1]".to_string());
+            }
+        }
+
+        if params.word_boundaries {
+            if params.match_mode != MatchMode::WholeString && params.concat_fields.is_none() {
+                return Err(
+                    "word_boundaries needs match_mode `whole_string` or concat_fields".to_string(),
+                );
+            }
+            if params.algorithm != Algorithm::Levenshtein {
+                return Err("word_boundaries is only supported by levenshtein".to_string());
+            }
+            if params.op_counts {
+                return Err("op_counts is not supported with word_boundaries".to_string());
+            }
+        }
+
+        if params.graphemes {
+            if !matches!(params.algorithm, Algorithm::Levenshtein | Algorithm::Lcs) {
+                return Err("graphemes is only supported by levenshtein and lcs".to_string());
+            }
+            if params.word_boundaries || params.op_counts {
+                return Err(
+                    "graphemes cannot be combined with word_boundaries or op_counts".into(),
+                );
+            }
+        }
+
+        let cost_matrix = if params.algorithm == Algorithm::Weighted {
+            if params.op_counts {
+                return Err("op_counts is not supported by weighted".to_string());
+            }
+            Some(
+                config
+                    .cost_matrix
+                    .clone()
+                    .ok_or("weighted needs FUZZY_COST_MATRIX on the server")?,
+            )
+        } else {
+            None
+        };
+
+        if params.sample && (params.stream || params.cursor.is_some()) {
+            return Err("sample cannot be combined with stream or cursor".to_string());
+        }
+
+        let facet_column = match &params.facet_by {
+            None => None,
+            Some(_) if params.stream || params.cursor.is_some() => {
+                return Err("facet_by cannot be combined with stream or cursor".to_string());
+            }
+            Some(name) => Some(
+                dataset
+                    .column(name)
+                    .ok_or_else(|| format!("unknown field `{name}`"))?,
+            ),
+        };
+
+        let join = if params.enrich {
+            let table = config
+                .join
+                .clone()
+                .ok_or("enrich needs FUZZY_JOIN_PATH on the server")?;
+            let key_column = config
+                .join_key
+                .as_deref()
+                .and_then(|column| dataset.column(column));
+            Some((table, key_column))
+        } else {
+            None
+        };
+
+        if let Some(min_score) = params.min_score {
+            if params.algorithm != Algorithm::NgramContainment {
+                return Err("min_score is only supported by ngram_containment".to_string());
+            }
+            if !(min_score > 0.0 && min_score <= 1.0) {
+                return Err("min_score must be in (0, 1]".to_string());
+            }
+            if params.max_distance.is_some() {
+                return Err("use either min_score or max_distance, not both".to_string());
+            }
+        }
+        match params.ngram_size {
+            Some(_) if params.algorithm != Algorithm::NgramContainment => {
+                return Err("ngram_size is only supported by ngram_containment".to_string());
+            }
+            Some(0) => return Err("ngram_size must be at least 1".to_string()),
+            _ => {}
+        }
+
+        if params.early_abort {
+            // con questi algoritmi distanza 0 vuol dire match esatto, quindi
+            // saltare la scansione fuzzy non cambia i risultati
+            if !matches!(
+                params.algorithm,
+                Algorithm::Levenshtein | Algorithm::Lcs | Algorithm::PhoneticFuzzy
+            ) {
+                return Err(
+                    "early_abort is only supported by levenshtein, lcs and phonetic_fuzzy"
+                        .to_string(),
+                );
+            }
+            if params.facet_by.is_some() || params.diversity.is_some() {
+                return Err("early_abort cannot be combined with facet_by or diversity".to_string());
+            }
+        }
+
+        if params.profile && params.stream {
+            return Err("profile is not supported with stream".to_string());
+        }
+
+        if params.length_filter
+            && (params.graphemes
+                || !matches!(
+                    params.algorithm,
+                    Algorithm::Levenshtein | Algorithm::Lcs | Algorithm::PhoneticFuzzy
+                ))
+        {
+            return Err(
+                "length_filter is only supported by levenshtein, lcs and phonetic_fuzzy, \
+                 without graphemes"
+                    .to_string(),
+            );
+        }
+
+        if params.group_by.is_some() && params.stream {
+            return Err("group_by is not supported with stream".to_string());
+        }
+
+        if params.rank_confidence && params.stream {
+            return Err("rank_confidence is not supported with stream".to_string());
+        }
+
+        if let Some(diversity) = params.diversity {
+            if !(0.0..=1.0).contains(&diversity) {
+                return Err("diversity must be in [0, 1]".to_string());
+            }
+            if params.sample || params.stream || params.cursor.is_some() {
+                return Err(
+                    "diversity cannot be combined with sample, stream or cursor".to_string()
+                );
+            }
+            if params.limit.unwrap_or(DEFAULT_LIMIT) > MAX_DIVERSITY_LIMIT {
+                return Err(format!(
+                    "diversity supports a limit of at most {MAX_DIVERSITY_LIMIT}"
+                ));
+            }
+        }
+
+        let params_hash = params_hash(&params, &query);
+        let after = match &params.cursor {
+            None => None,
+            Some(_) if params.stream => {
+                return Err("cursor is not supported with stream".to_string());
+            }
+            Some(_) if params.query.trim().is_empty() => {
+                return Err("cursor is not supported for empty queries".to_string());
+            }
+            Some(text) => {
+                let cursor = Cursor::decode(text).ok_or("invalid cursor")?;
+                if cursor.version != dataset.version {
+                    return Err(format!(
+                        "cursor is for dataset version {}, now {}: start again from the first page",
+                        cursor.version, dataset.version
+                    ));
+                }
+                if cursor.params_hash != params_hash {
+                    return Err(
+                        "cursor belongs to a different query or different parameters".to_string(),
+                    );
+                }
+                Some((cursor.distance, cursor.index))
+            }
+        };
+
+        let not_before = match (&params.since, params.recency_days) {
+            (None, None) => None,
+            (Some(_), Some(_)) => {
+                return Err("use either since or recency_days, not both".to_string());
+            }
+            _ if dataset.timestamp_column.is_none() => {
+                return Err("since and recency_days need FUZZY_TIMESTAMP_COLUMN".to_string());
+            }
+            (Some(since), None) => {
+                Some(parse_timestamp(since).ok_or_else(|| format!("invalid since `{since}`"))?)
+            }
+            (None, Some(days)) if days > MAX_RECENCY_DAYS => {
+                return Err(format!("recency_days must be at most {MAX_RECENCY_DAYS}"));
+            }
+            (None, Some(days)) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_secs() as i64);
+                Some(now - days as i64 * 86_400)
+            }
+        };
+
+        // caratteri distinti della query in minuscolo, spazi esclusi; le query
+        // vuote hanno già la loro gestione
+        let distinct_chars = {
+            let mut chars: Vec<char> = fold_case(&params.query)
+                .chars()
+                .filter(|char| !char.is_whitespace())
+                .collect();
+            chars.sort_unstable();
+            chars.dedup();
+            chars.len()
+        };
+        let warning =
+            (distinct_chars > 0 && distinct_chars < config.min_distinct_chars).then(|| {
+                format!(
+                    "query has {distinct_chars} distinct characters, fewer than the {} needed \
+                 for useful matches",
+                    config.min_distinct_chars
+                )
+            });
+        let rejected = warning.is_some() && config.low_variety_policy == LowVarietyPolicy::Reject;
+
+        let first_char = if params.require_first_char {
+            query.chars().next()
+        } else {
+            None
+        };
+
+        let query_soundex = soundex(&query);
+
+        let query_ngrams = if params.algorithm == Algorithm::NgramContainment {
+            if params.op_counts {
+                return Err("op_counts is not supported by ngram_containment".to_string());
+            }
+            let grams = ngrams(&query, params.ngram_size.unwrap_or(DEFAULT_NGRAM_SIZE));
+            // `min_score` diventa il numero massimo di n-grammi mancanti; almeno
+            // uno deve esserci comunque, containment 0 non è un match
+            let allowed = match params.max_distance {
+                Some(max_distance) => max_distance,
+                None => {
+                    let min_score = params.min_score.unwrap_or(DEFAULT_MIN_SCORE);
+                    ((1.0 - min_score) * grams.len() as f64 + 1e-9).floor() as usize
+                }
+            };
+            max_distance = allowed.min(grams.len() - 1);
+            Some(grams)
+        } else {
+            None
+        };
+        if params.seed.is_some_and(|seed| seed > MAX_SEED) {
+            return Err(format!("seed must be at most {MAX_SEED}"));
+        }
+        // sempre sotto 2^53, così il seme restituito resta esatto in JavaScript
+        let seed = params
+            .seed
+            .or_else(|| params.sample.then(|| rand::random::<u64>() & MAX_SEED));
+        Ok(Search {
+            params,
+            query,
+            target,
+            first_char,
+            empty_query: config.empty_query,
+            frequency_column: config
+                .frequency_column
+                .as_deref()
+                .and_then(|column| dataset.column(column)),
+            parallelism,
+            limit,
+            max_distance,
+            cost_matrix,
+            after,
+            params_hash,
+            not_before,
+            missing_timestamp: config.missing_timestamp,
+            arm,
+            canary_fallback: None,
+            facet_column,
+            join,
+            query_soundex,
+            query_ngrams,
+            dp_cells: AtomicU64::new(0),
+            seed,
+            warning,
+            rejected,
+        })
+    }
+
+    fn accepts(&self, record: &Record) -> bool {
+        self.params
+            .since_version
+            .is_none_or(|version| record.version > version)
+            && self
+                .not_before
+                .is_none_or(|not_before| match record.timestamp {
+                    Some(timestamp) => timestamp >= not_before,
+                    None => self.missing_timestamp == MissingTimestamp::Include,
+                })
+    }
+
+    fn candidate<'r>(&self, record: &'r Record) -> Cow<'r, str> {
+        match &self.target {
+            Target::Name => Cow::Borrowed(record.key(self.params.ignore_case)),
+            Target::Concat { fields, separator } => {
+                let joined = fields
+                    .iter()
+                    .map(|&index| record.field(index))
+                    .collect::<Vec<_>>()
+                    .join(separator);
+                if self.params.ignore_case {
+                    Cow::Owned(fold_case(&joined).into_owned())
+                } else {
+                    Cow::Owned(joined)
+                }
+            }
+        }
+    }
+
+    fn match_candidate(&self, candidate: &str) -> Match {
+        match self.target {
+            Target::Name => self.fuzzy_match(candidate),
+            // la stringa concatenata si confronta per intero, così la query
+            // può attraversare il confine tra due campi
+            Target::Concat { .. } => self.match_token(candidate),
+        }
+    }
+
+    // Miglior match tra il nome (o i campi concatenati), gli alias e la
+    // forma traslitterata
+    fn match_record(&self, record: &Record) -> Match {
+        let candidate = self.candidate(record);
+        let mut best = self.match_candidate(&candidate);
+
+        if matches!(self.target, Target::Name) {
+            for (index, alias) in record.aliases.iter().enumerate() {
+                let found = self.fuzzy_match(alias.key(self.params.ignore_case));
+                if found.distance < best.distance {
+                    best = Match {
+                        alias: Some(index),
+                        ..found
+                    };
+                }
+            }
+
+            if let Some(transliterated) = &record.transliterated {
+                let found = self.fuzzy_match(transliterated.key(self.params.ignore_case));
+                if found.distance < best.distance {
+                    best = Match {
+                        transliterated: true,
+                        ..found
+                    };
+                }
+            }
+        }
+
+        best
+    }
+
+    fn exact_record(&self, record: &Record) -> Option<Match> {
+        let exact = |candidate: &str, alias| Match {
+            distance: 0,
+            matched_start: 0,
+            matched_len: self.query.len(),
+            candidate_len: candidate.len(),
+            alias,
+            transliterated: false,
+        };
+
+        let candidate = self.candidate(record);
+        if self.is_exact(&candidate) {
+            return Some(exact(&candidate, None));
+        }
+
+        if !matches!(self.target, Target::Name) {
+            return None;
+        }
+
+        record
+            .aliases
+            .iter()
+            .enumerate()
+            .find_map(|(index, alias)| {
+                let key = alias.key(self.params.ignore_case);
+                self.is_exact(key).then(|| exact(key, Some(index)))
+            })
+            .or_else(|| {
+                let key = record.transliterated.as_ref()?.key(self.params.ignore_case);
+                self.is_exact(key).then(|| Match {
+                    transliterated: true,
+                    ..exact(key, None)
+                })
+            })
+    }
+
+    fn fuzzy_match(&self, full_name: &str) -> Match {
+        // gli n-grammi possono attraversare gli spazi: si confronta sempre il testo intero
+        if self.params.match_mode == MatchMode::WholeString
+            || self.query_ngrams.is_some()
+            || !full_name.contains(' ')
+        {
+            return self.match_token(full_name);
+        }
+
+        let found = full_name
+            .split_whitespace()
+            .map(|part| Match {
+                matched_start: part.as_ptr() as usize - full_name.as_ptr() as usize,
+                ..self.match_token(part)
+            })
+            .min_by_key(|found| found.distance)
+            .unwrap_or(Match::NONE);
+
+        // a parità vince il token, come in `tokens`
+        if self.params.match_mode == MatchMode::Combined {
+            let whole = self.match_token(full_name);
+            if whole.distance < found.distance {
+                return whole;
+            }
+        }
+
+        Match {
+            candidate_len: full_name.len(),
+            ..found
+        }
+    }
+
+    fn match_token(&self, token: &str) -> Match {
+        // pre-filtro economico: il primo carattere deve coincidere
+        if self
+            .first_char
+            .is_some_and(|first_char| !token.starts_with(first_char))
+        {
+            return Match::NONE;
+        }
+
+        // nessuno di questi algoritmi fa meno di una modifica per byte di differenza
+        if self.params.length_filter && token.len().abs_diff(self.query.len()) > self.max_distance {
+            return Match::NONE;
+        }
+
+        // il secchiello fonetico costa una passata sul token, il DP molto di più
+        if self.params.algorithm == Algorithm::PhoneticFuzzy && soundex(token) != self.query_soundex
+        {
+            return Match::NONE;
+        }
+
+        let distance = if self.params.word_boundaries {
+            calc_dist_word_boundaries(self.query.as_bytes(), token.as_bytes()).unwrap_or(usize::MAX)
+        } else if let Some(min_density) = self.params.min_density {
+            // match troppo sparsi (query sparpagliata su una finestra ampia) scartati
+            match subsequence_window(self.query.as_bytes(), token.as_bytes()) {
+                Some((start, end))
+                    if self.query.len() as f64 >= min_density * (end - start) as f64 =>
+                {
+                    end - start - self.query.len()
+                }
+                _ => usize::MAX,
+            }
+        } else if self.params.graphemes {
+            self.params.algorithm.grapheme_distance(&self.query, token)
+        } else if let Some(cost_matrix) = &self.cost_matrix {
+            cost_matrix.distance(&self.query, token)
+        } else if let Some(grams) = &self.query_ngrams {
+            missing_ngrams(grams, token)
+        } else {
+            self.params
+                .algorithm
+                .distance(self.query.as_bytes(), token.as_bytes())
+        };
+
+        Match {
+            distance,
+            matched_start: 0,
+            matched_len: token.len(),
+            candidate_len: token.len(),
+            alias: None,
+            transliterated: false,
+        }
+    }
+
+    fn is_exact(&self, candidate: &str) -> bool {
+        let query_bytes = self.query.as_bytes();
+
+        candidate.as_bytes() == query_bytes
+            || matches!(self.target, Target::Name)
+                && self.params.match_mode != MatchMode::WholeString
+                && candidate
+                    .split_whitespace()
+                    .any(|part| part.as_bytes() == query_bytes)
+    }
+
+    // Query corretta token per token con il vocabolario del dataset, per i
+    // suggerimenti "cerca invece X"; `None` se non cambierebbe nulla
+    pub fn suggestion(&self, dataset: &Dataset) -> Option<String> {
+        if self.rejected {
+            return None;
+        }
+        let query = fold_case(&self.params.query);
+        let corrected = query
+            .split_whitespace()
+            .map(|token| {
+                dataset
+                    .vocabulary
+                    .nearest(token, self.max_distance)
+                    .unwrap_or(token)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        (!corrected.is_empty() && corrected != query.trim()).then_some(corrected)
+    }
+
+    // Match per valore del campo `facet_by`; va chiamata prima di tagliare a
+    // `limit`, quando `results` contiene ancora tutti i match
+    pub fn facets(
+        &self,
+        dataset: &Dataset,
+        results: &[SearchResult],
+    ) -> Option<BTreeMap<String, usize>> {
+        let column = self.facet_column?;
+        let mut counts = BTreeMap::new();
+        for result in results {
+            let value = dataset.records[result.index].field(column);
+            *counts.entry(value.to_string()).or_insert(0) += 1;
+        }
+        Some(counts)
+    }
+
+    // Celle DP calcolate finora dalle scansioni di questa richiesta
+    pub fn dp_cells(&self) -> Option<u64> {
+        self.params
+            .profile
+            .then(|| self.dp_cells.load(Ordering::Relaxed))
+    }
+
+    // Cursore per la pagina successiva, a partire dall'ultimo risultato di
+    // questa; le query vuote non si paginano
+    pub fn next_cursor(&self, results: &[SearchResult], version: u64) -> Option<String> {
+        if self.is_empty_query() {
+            return None;
+        }
+
+        results.last().map(|last| {
+            Cursor {
+                version,
+                params_hash: self.params_hash,
+                distance: last.distance,
+                index: last.index,
+            }
+            .encode()
+        })
+    }
+
+    // Al posto del semplice taglio a `limit`: i risultati con distanza minore
+    // di quella dell'ultimo posto restano tutti, i posti rimanenti si estraggono
+    // tra i pari merito pesando per score (Efraimidis–Spirakis, chiave
+    // u^(1/score)). `results` va passato ordinato per distanza; restituisce
+    // se c'è stata un'estrazione vera, cioè più pari merito che posti.
+    pub fn sample(&self, results: &mut Vec<SearchResult>) -> bool {
+        let Some(boundary) = results.get(self.limit.saturating_sub(1)) else {
+            return false;
+        };
+        let boundary = boundary.distance;
+        let kept = results.partition_point(|result| result.distance < boundary);
+        let tied = results[kept..].partition_point(|result| result.distance == boundary);
+        let slots = self.limit - kept;
+        if tied <= slots {
+            results.truncate(self.limit);
+            return false;
+        }
+
+        let mut rng = self.rng(RngStream::Sample);
+        let mut keyed: Vec<(f64, SearchResult)> = results
+            .drain(kept..)
+            .take(tied)
+            .map(|result| {
+                let weight = result.score.value().max(f64::EPSILON);
+                let u = 1.0 - rng.random::<f64>();
+                (u.powf(1.0 / weight), result)
+            })
+            .collect();
+        keyed.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        results.extend(keyed.into_iter().take(slots).map(|(_, result)| result));
+        true
+    }
+
+    // Generatore di una componente casuale, da `seed`: ogni componente ha il
+    // suo flusso, così aggiungerne una non cambia le estrazioni delle altre
+    fn rng(&self, stream: RngStream) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed.unwrap_or_default());
+        rng.set_stream(stream as u64);
+        rng
+    }
+
+    // Riordino MMR (maximal marginal relevance): a ogni passo si sceglie il
+    // risultato con il miglior compromesso tra score e distanza dai nomi già
+    // scelti, `(1 - weight) * score - weight * max_similarità`. La somiglianza è
+    // 1 - Levenshtein normalizzato tra i nomi in minuscolo. `results` va
+    // passato ordinato per distanza; ne restano `limit`, nell'ordine di scelta.
+    pub fn diversify(&self, results: &mut Vec<SearchResult>, weight: f64) {
+        results.truncate(DIVERSITY_POOL);
+        let names: Vec<String> = results
+            .iter()
+            .map(|result| fold_case(&result.name).into_owned())
+            .collect();
+        let similarity = |a: usize, b: usize| {
+            let longest = names[a].len().max(names[b].len()).max(1);
+            1.0 - calc_dist_bytes(names[a].as_bytes(), names[b].as_bytes()) as f64 / longest as f64
+        };
+
+        let mut selected: Vec<usize> = Vec::with_capacity(self.limit);
+        // somiglianza massima di ogni candidato con quelli già scelti
+        let mut max_similarity = vec![0.0f64; results.len()];
+        let mut remaining: Vec<usize> = (0..results.len()).collect();
+
+        while selected.len() < self.limit && !remaining.is_empty() {
+            let marginal = |index: usize| {
+                (1.0 - weight) * results[index].score.value() - weight * max_similarity[index]
+            };
+            // a parità vince il primo nell'ordine per distanza
+            let position = (0..remaining.len())
+                .rev()
+                .max_by(|&a, &b| marginal(remaining[a]).total_cmp(&marginal(remaining[b])))
+                .unwrap_or(0);
+            let chosen = remaining.remove(position);
+            for &index in &remaining {
+                max_similarity[index] = max_similarity[index].max(similarity(index, chosen));
+            }
+            selected.push(chosen);
+        }
+
+        let mut pool: Vec<Option<SearchResult>> = results.drain(..).map(Some).collect();
+        results.extend(selected.into_iter().filter_map(|index| pool[index].take()));
+    }
+
+    fn is_empty_query(&self) -> bool {
+        self.params.query.trim().is_empty()
+    }
+
+    pub fn exact_matches(&self, dataset: &Dataset) -> Vec<SearchResult> {
+        if self.rejected {
+            return Vec::new();
+        }
+        if self.is_empty_query() {
+            return self.browse(dataset);
+        }
+
+        dataset
+            .records
+            .par_iter()
+            .enumerate()
+            .filter(|(index, record)| {
+                self.accepts(record) && self.after.is_none_or(|after| (0, *index) > after)
+            })
+            .filter_map(|(index, record)| {
+                self.exact_record(record)
+                    .map(|found| self.result(dataset, index, record, &found))
+            })
+            .collect()
+    }
+
+    // Con `skip_exact` esclude i record già restituiti da `exact_matches`:
+    // non basta scartare la distanza 0, che con `subsequence` o `weighted`
+    // non implica l'uguaglianza
+    pub fn fuzzy_matches(&self, dataset: &Dataset, skip_exact: bool) -> Vec<SearchResult> {
+        if self.rejected {
+            return Vec::new();
+        }
+        if self.is_empty_query() {
+            // i risultati di una query vuota contano tutti come esatti
+            return if !skip_exact {
+                self.browse(dataset)
+            } else {
+                Vec::new()
+            };
+        }
+
+        dataset
+            .records
+            .par_iter()
+            .enumerate()
+            .filter(|(_, record)| self.accepts(record))
+            .filter_map(|(index, record)| {
+                let before = self.params.profile.then(dp_cells);
+                let found = self.match_record(record);
+                let result = if found.distance > self.max_distance
+                    || self
+                        .after
+                        .is_some_and(|after| (found.distance, index) <= after)
+                    || skip_exact && found.distance == 0 && self.exact_record(record).is_some()
+                {
+                    None
+                } else {
+                    Some(self.result(dataset, index, record, &found))
+                };
+                if let Some(before) = before {
+                    self.dp_cells
+                        .fetch_add(dp_cells().wrapping_sub(before), Ordering::Relaxed);
+                }
+                result
+            })
+            .collect()
+    }
+
+    // Query vuota: niente per default, altrimenti i nomi più frequenti o i
+    // primi in ordine alfabetico
+    fn browse(&self, dataset: &Dataset) -> Vec<SearchResult> {
+        let mut records: Vec<(usize, &Record)> = match self.empty_query {
+            EmptyQuery::None => return Vec::new(),
+            EmptyQuery::Popular | EmptyQuery::Alphabetical => dataset
+                .records
+                .par_iter()
+                .enumerate()
+                .filter(|(_, record)| self.accepts(record))
+                .collect(),
+        };
+
+        match (self.empty_query, self.frequency_column) {
+            (EmptyQuery::Popular, Some(column)) => {
+                let frequency = |record: &Record| record.field(column).parse::<u64>().unwrap_or(0);
+                records.par_sort_by(|(_, a), (_, b)| {
+                    frequency(b)
+                        .cmp(&frequency(a))
+                        .then_with(|| a.name.cmp(&b.name))
+                });
+            }
+            _ => records.par_sort_by(|(_, a), (_, b)| a.name.cmp(&b.name)),
+        }
+
+        records
+            .into_iter()
+            .take(self.limit)
+            .map(|(index, record)| {
+                let found = Match {
+                    distance: 0,
+                    matched_start: 0,
+                    matched_len: 0,
+                    candidate_len: record.name.len(),
+                    alias: None,
+                    transliterated: false,
+                };
+                self.result(dataset, index, record, &found)
+            })
+            .collect()
+    }
+
+    fn result(
+        &self,
+        dataset: &Dataset,
+        index: usize,
+        record: &Record,
+        found: &Match,
+    ) -> SearchResult {
+        let fields = if dataset.is_multi_field() {
+            dataset
+                .columns
+                .iter()
+                .cloned()
+                .zip(record.fields.iter().cloned())
+                .collect()
+        } else {
+            BTreeMap::new()
+        };
+
+        SearchResult {
+            name: record.name.clone(),
+            distance: found.distance,
+            score: Score::new(
+                self.params.score_scale,
+                match &self.query_ngrams {
+                    // la containment stessa, qualunque sia `score_base`
+                    Some(grams) => 1.0 - found.distance as f64 / grams.len() as f64,
+                    None => {
+                        let (query_len, name_len, matched_len) = self.score_lengths(record, found);
+                        score(
+                            self.params.score_base,
+                            found.distance,
+                            query_len,
+                            name_len,
+                            matched_len,
+                        )
+                    }
+                },
+            ),
+            row_hash: record.row_hash(),
+            fields,
+            matched_alias: found.alias.map(|index| record.aliases[index].text.clone()),
+            transliterated: found.transliterated,
+            op_counts: self.params.op_counts.then(|| self.op_counts(record, found)),
+            line_number: record.line_number.filter(|_| self.params.line_numbers),
+            attributes: self.join.as_ref().map(|(table, key_column)| {
+                table.lookup(key_column.map_or(&record.name, |column| &record.fields[column]))
+            }),
+            matched_text: self
+                .params
+                .autocorrect
+                .then(|| self.matched_text(record, found).into_owned()),
+            index,
+        }
+    }
+
+    // Ricalcola l'allineamento solo per i risultati restituiti, rileggendo il
+    // token che ha dato la distanza minima
+    fn op_counts(&self, record: &Record, found: &Match) -> EditOps {
+        if found.distance == 0 {
+            return EditOps::default();
+        }
+
+        let matched = self.matched_text(record, found);
+        EditOps::new(
+            self.params.algorithm,
+            self.query.as_bytes(),
+            matched.as_bytes(),
+        )
+    }
+
+    // I match esatti non registrano la posizione del token, ma a distanza 0
+    // vale la query stessa
+    fn matched_text<'r>(&'r self, record: &'r Record, found: &Match) -> Cow<'r, str> {
+        if found.distance == 0 {
+            return Cow::Borrowed(&self.query);
+        }
+
+        match self.matched_candidate(record, found) {
+            Cow::Borrowed(text) => Cow::Borrowed(&text[found.matched_start..][..found.matched_len]),
+            Cow::Owned(text) => {
+                Cow::Owned(text[found.matched_start..][..found.matched_len].to_string())
+            }
+        }
+    }
+
+    // Nome, alias o forma traslitterata che ha dato il match
+    fn matched_candidate<'r>(&'r self, record: &'r Record, found: &Match) -> Cow<'r, str> {
+        match (found.alias, &record.transliterated) {
+            (Some(index), _) => Cow::Borrowed(record.aliases[index].key(self.params.ignore_case)),
+            (None, Some(transliterated)) if found.transliterated => {
+                Cow::Borrowed(transliterated.key(self.params.ignore_case))
+            }
+            _ => self.candidate(record),
+        }
+    }
+
+    // Lunghezze di query, candidato e token per lo score, nella stessa unità
+    // della distanza: byte, o grapheme cluster con `graphemes` (a distanza 0
+    // lo score è 1 comunque, e i match esatti non hanno `matched_start`)
+    fn score_lengths(&self, record: &Record, found: &Match) -> (usize, usize, usize) {
+        if !self.params.graphemes || found.distance == 0 {
+            return (self.query.len(), found.candidate_len, found.matched_len);
+        }
+        let candidate = self.matched_candidate(record, found);
+        let matched = &candidate[found.matched_start..][..found.matched_len];
+        (
+            graphemes(&self.query).len(),
+            graphemes(&candidate).len(),
+            graphemes(matched).len(),
+        )
+    }
+
+    // Correzione da applicare senza chiedere: il miglior match deve essere
+    // vicino e nettamente meglio del secondo testo distinto. `results` va
+    // passato ordinato per distanza e prima del taglio a `limit`.
+    pub fn autocorrect(&self, results: &[SearchResult]) -> Option<String> {
+        let best = results.first()?;
+        if best.distance == 0 || best.distance > AUTOCORRECT_MAX_DISTANCE {
+            return None;
+        }
+        let text = best.matched_text.as_ref()?;
+
+        let runner_up = results
+            .iter()
+            .find(|result| result.matched_text.as_ref() != Some(text));
+        if runner_up.is_some_and(|result| result.distance < best.distance + AUTOCORRECT_MARGIN) {
+            return None;
+        }
+
+        Some(text.clone())
+    }
+}
+
+// Probabilità che il primo risultato sia quello giusto: softmax degli score
+// dei risultati restituiti, exp(s1 / T) / Σ exp(si / T). Si sottrae lo score
+// massimo prima dell'esponenziale per non andare in overflow.
+pub fn rank_confidence(results: &[SearchResult]) -> Option<f64> {
+    let top = results.first()?.score.value();
+    let max = results
+        .iter()
+        .map(|result| result.score.value())
+        .fold(f64::MIN, f64::max);
+    let weight = |score: f64| ((score - max) / RANK_CONFIDENCE_TEMPERATURE).exp();
+    let total: f64 = results
+        .iter()
+        .map(|result| weight(result.score.value()))
+        .sum();
+
+    Some(weight(top) / total)
+}
+
+fn score(
+    base: ScoreBase,
+    distance: usize,
+    query_len: usize,
+    name_len: usize,
+    matched_len: usize,
+) -> f64 {
+    let denominator = match base {
+        ScoreBase::Max => query_len.max(name_len),
+        ScoreBase::Query => query_len,
+        ScoreBase::Matched => matched_len,
+    };
+
+    if denominator == 0 {
+        return if distance == 0 { 1.0 } else { 0.0 };
+    }
+
+    (1.0 - distance as f64 / denominator as f64).max(0.0)
+}
+
+struct Match {
+    distance: usize,
+    // posizione in byte del token nel candidato, per rileggerlo dopo
+    matched_start: usize,
+    // lunghezza in byte del token (o del nome intero) che ha dato la distanza minima
+    matched_len: usize,
+    // lunghezza del nome, alias o stringa concatenata a cui appartiene il token
+    candidate_len: usize,
+    alias: Option<usize>,
+    transliterated: bool,
+}
+
+impl Match {
+    const NONE: Match = Match {
+        distance: usize::MAX,
+        matched_start: 0,
+        matched_len: 0,
+        candidate_len: 0,
+        alias: None,
+        transliterated: false,
+    };
+}
@@ -0,0 +1,95 @@
+// Istanti come secondi Unix. Formati accettati: un intero (secondi Unix),
+// `YYYY-MM-DD` (mezzanotte UTC) oppure `YYYY-MM-DDTHH:MM:SS` con frazioni di
+// secondo opzionali e fuso `Z` o `±HH:MM` (senza fuso vale UTC); al posto
+// della `T` va bene anche uno spazio. Anni fuori da 0..=9999 e fusi oltre
+// ±23:59 non sono accettati, così i calcoli non possono andare in overflow.
+pub fn parse_timestamp(text: &str) -> Option<i64> {
+    let text = text.trim();
+    if let Ok(seconds) = text.parse::<i64>() {
+        return Some(seconds);
+    }
+
+    let (date, time) = match text.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (text, None),
+    };
+
+    let mut parts = date.splitn(3, '-');
+    let year = parts.next()?.parse::<i64>().ok()?;
+    let month = parts.next()?.parse::<u32>().ok()?;
+    let day = parts.next()?.parse::<u32>().ok()?;
+    if !(0..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+    {
+        return None;
+    }
+    let mut seconds = days_from_civil(year, month, day) * 86_400;
+
+    if let Some(time) = time {
+        let (clock, offset) = split_offset(time)?;
+        let mut parts = clock.splitn(3, ':');
+        let hours = parts.next()?.parse::<i64>().ok()?;
+        let minutes = parts.next()?.parse::<i64>().ok()?;
+        // le frazioni di secondo si scartano
+        let whole_seconds = match parts.next() {
+            Some(value) => value.split('.').next()?.parse::<i64>().ok()?,
+            None => 0,
+        };
+        if !(0..=23).contains(&hours)
+            || !(0..=59).contains(&minutes)
+            || !(0..=60).contains(&whole_seconds)
+        {
+            return None;
+        }
+        seconds += hours * 3600 + minutes * 60 + whole_seconds - offset;
+    }
+
+    Some(seconds)
+}
+
+// Separa l'orario dal fuso, restituendo lo scarto dall'UTC in secondi
+fn split_offset(time: &str) -> Option<(&str, i64)> {
+    if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        return Some((clock, 0));
+    }
+    match time.rfind(['+', '-']) {
+        Some(index) => {
+            let sign = if time[index..].starts_with('-') {
+                -1
+            } else {
+                1
+            };
+            let (hours, minutes) = time[index + 1..].split_once(':')?;
+            let hours = hours.parse::<i64>().ok()?;
+            let minutes = minutes.parse::<i64>().ok()?;
+            if !(0..=23).contains(&hours) || !(0..=59).contains(&minutes) {
+                return None;
+            }
+            Some((&time[..index], sign * (hours * 3600 + minutes * 60)))
+        }
+        None => Some((time, 0)),
+    }
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Giorni dal 1970-01-01 (algoritmo "days from civil" di Howard Hinnant)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
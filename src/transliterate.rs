@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+// Tabella incorporata: cirillico (russo e ucraino) e greco, in una
+// traslitterazione semplice senza diacritici
+#[rustfmt::skip]
+const BUILTIN: &[(char, &str)] = &[
+    ('а', "a"), ('б', "b"), ('в', "v"), ('г', "g"), ('д', "d"), ('е', "e"),
+    ('ё', "e"), ('ж', "zh"), ('з', "z"), ('и', "i"), ('й', "y"), ('к', "k"),
+    ('л', "l"), ('м', "m"), ('н', "n"), ('о', "o"), ('п', "p"), ('р', "r"),
+    ('с', "s"), ('т', "t"), ('у', "u"), ('ф', "f"), ('х', "kh"), ('ц', "ts"),
+    ('ч', "ch"), ('ш', "sh"), ('щ', "shch"), ('ъ', ""), ('ы', "y"), ('ь', ""),
+    ('э', "e"), ('ю', "yu"), ('я', "ya"), ('і', "i"), ('ї', "yi"), ('є', "ye"),
+    ('ґ', "g"),
+    ('α', "a"), ('β', "v"), ('γ', "g"), ('δ', "d"), ('ε', "e"), ('ζ', "z"),
+    ('η', "i"), ('θ', "th"), ('ι', "i"), ('κ', "k"), ('λ', "l"), ('μ', "m"),
+    ('ν', "n"), ('ξ', "x"), ('ο', "o"), ('π', "p"), ('ρ', "r"), ('σ', "s"),
+    ('ς', "s"), ('τ', "t"), ('υ', "y"), ('φ', "f"), ('χ', "ch"), ('ψ', "ps"),
+    ('ω', "o"), ('ά', "a"), ('έ', "e"), ('ή', "i"), ('ί', "i"), ('ό', "o"),
+    ('ύ', "y"), ('ώ', "o"), ('ϊ', "i"), ('ϋ', "y"), ('ΐ', "i"), ('ΰ', "y"),
+];
+
+// Caratteri minuscoli -> forma latina; le maiuscole si cercano in minuscolo
+// e ricevono l'iniziale maiuscola
+#[derive(Clone)]
+pub struct Transliterator {
+    table: HashMap<char, String>,
+}
+
+impl Transliterator {
+    pub fn builtin() -> Transliterator {
+        Transliterator {
+            table: BUILTIN
+                .iter()
+                .map(|&(from, to)| (from, to.to_string()))
+                .collect(),
+        }
+    }
+
+    // Un oggetto JSON `{"ж": "j", ...}` che estende o sostituisce la tabella
+    // incorporata
+    pub fn load(path: &str) -> Result<Transliterator, String> {
+        let text = std::fs::read_to_string(path).map_err(|error| {
+            format!("cannot read FUZZY_TRANSLITERATION_TABLE `{path}`: {error}")
+        })?;
+        let entries: HashMap<String, String> = serde_json::from_str(&text)
+            .map_err(|error| format!("invalid FUZZY_TRANSLITERATION_TABLE `{path}`: {error}"))?;
+
+        let mut transliterator = Transliterator::builtin();
+        for (key, value) in entries {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(from), None) => {
+                    transliterator.table.insert(lowercase(from), value);
+                }
+                _ => {
+                    return Err(format!(
+                        "FUZZY_TRANSLITERATION_TABLE key `{key}` must be a single character"
+                    ))
+                }
+            }
+        }
+        Ok(transliterator)
+    }
+
+    // `None` se nessun carattere è nella tabella: il testo è già latino
+    pub fn apply(&self, text: &str) -> Option<String> {
+        let mut output = String::with_capacity(text.len());
+        let mut changed = false;
+
+        for c in text.chars() {
+            match self.table.get(&lowercase(c)) {
+                Some(latin) => {
+                    changed = true;
+                    let mut latin = latin.chars();
+                    if c.is_uppercase() {
+                        if let Some(first) = latin.next() {
+                            output.extend(first.to_uppercase());
+                        }
+                    }
+                    output.extend(latin);
+                }
+                None => output.push(c),
+            }
+        }
+
+        changed.then_some(output)
+    }
+}
+
+// Minuscola di un solo carattere (le eccezioni multi-carattere restano tali)
+fn lowercase(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => c,
+    }
+}
@@ -0,0 +1,69 @@
+use std::{collections::BTreeMap, mem::size_of};
+
+use rayon::prelude::*;
+
+use crate::{distance::calc_dist_bytes, heap_size::HeapSize};
+
+// Token distinti (in minuscolo) di nomi e alias, con il numero di occorrenze
+#[derive(Default)]
+pub struct Vocabulary {
+    tokens: BTreeMap<String, u32>,
+}
+
+impl Vocabulary {
+    pub fn add(&mut self, text: &str) {
+        for token in text.split_whitespace() {
+            *self.tokens.entry(token.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    pub fn merge(mut self, mut other: Vocabulary) -> Vocabulary {
+        if self.tokens.len() < other.tokens.len() {
+            std::mem::swap(&mut self, &mut other);
+        }
+        for (token, count) in other.tokens {
+            *self.tokens.entry(token).or_insert(0) += count;
+        }
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn tokens(&self) -> impl Iterator<Item = &str> {
+        self.tokens.keys().map(String::as_str)
+    }
+
+    // Token più vicino entro `max_distance`; a parità di distanza vince il più
+    // frequente, poi l'ordine alfabetico
+    pub fn nearest(&self, token: &str, max_distance: usize) -> Option<&str> {
+        if self.tokens.contains_key(token) {
+            return Some(self.tokens.get_key_value(token)?.0);
+        }
+
+        self.tokens
+            .par_iter()
+            .filter(|(candidate, _)| candidate.len().abs_diff(token.len()) <= max_distance)
+            .map(|(candidate, &count)| {
+                (
+                    calc_dist_bytes(token.as_bytes(), candidate.as_bytes()),
+                    count,
+                    candidate,
+                )
+            })
+            .filter(|&(distance, _, _)| distance <= max_distance)
+            .min_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)).then(a.2.cmp(b.2)))
+            .map(|(_, _, candidate)| candidate.as_str())
+    }
+}
+
+impl HeapSize for Vocabulary {
+    // BTreeMap non espone la capacità: si contano chiavi e valori
+    fn heap_size(&self) -> usize {
+        self.tokens
+            .keys()
+            .map(|token| token.heap_size() + size_of::<String>() + size_of::<u32>())
+            .sum()
+    }
+}
@@ -0,0 +1,59 @@
+use std::{hint::black_box, time::Instant};
+
+use crate::dataset::{Dataset, Record};
+
+pub struct WarmUpReport {
+    pub duration_ms: u64,
+    pub memory_locked: bool,
+}
+
+// Legge ogni byte dei record (alias e forme normalizzate, minuscole e
+// traslitterate comprese) e dei token del vocabolario, così le prime ricerche
+// non pagano i page fault; con `lock` prova anche a bloccare la memoria in RAM
+pub fn warm_up(dataset: &Dataset, lock: bool) -> WarmUpReport {
+    let start_time = Instant::now();
+
+    let checksum = dataset
+        .records
+        .iter()
+        .flat_map(Record::strings)
+        .chain(dataset.vocabulary.tokens())
+        .flat_map(str::bytes)
+        .fold(0u64, |checksum, byte| checksum.wrapping_add(byte as u64));
+    black_box(checksum);
+
+    let memory_locked = lock
+        && match lock_memory() {
+            Ok(()) => true,
+            Err(error) => {
+                eprintln!("mlock not available, continuing without it: {error}");
+                false
+            }
+        };
+
+    WarmUpReport {
+        duration_ms: start_time.elapsed().as_millis() as u64,
+        memory_locked,
+    }
+}
+
+#[cfg(unix)]
+fn lock_memory() -> std::io::Result<()> {
+    // solo le pagine attuali: con MCL_FUTURE anche i pool, gli stack e i record
+    // aggiunti dopo sarebbero bloccati, e oltre RLIMIT_MEMLOCK le allocazioni
+    // fallirebbero invece di andare in swap
+    // SAFETY: mlockall non tocca memoria di Rust, cambia solo il paging del processo
+    if unsafe { libc::mlockall(libc::MCL_CURRENT) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+fn lock_memory() -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "mlockall is only available on unix",
+    ))
+}
@@ -0,0 +1,183 @@
+use std::sync::RwLock;
+
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws::{handshake, CloseCode, Codec, Frame, Message, ProtocolError};
+use actix_web::{
+    body::{BodyStream, BoxBody},
+    rt::{spawn, task::spawn_blocking},
+    web::{Bytes, BytesMut, Data, Payload},
+    Error, HttpRequest, HttpResponse,
+};
+use futures_util::{stream, StreamExt};
+use serde::Serialize;
+use tokio::sync::{mpsc, watch};
+
+use crate::{
+    config::Config,
+    dataset::Dataset,
+    forced_arm, oversize_message,
+    pools::ThreadPools,
+    progress::LoadProgress,
+    run_search,
+    search::{Arm, Search, SearchParams},
+    still_loading, SearchResponse,
+};
+
+// Ultima query ricevuta sulla connessione, con il suo numero progressivo
+type LatestQuery = Option<(u64, String)>;
+
+#[derive(Serialize)]
+struct WsResponse {
+    seq: u64,
+    #[serde(flatten)]
+    response: SearchResponse,
+}
+
+#[derive(Serialize)]
+struct WsError {
+    seq: u64,
+    error: String,
+}
+
+// Typeahead su WebSocket: ogni messaggio di testo è un `SearchParams` JSON e
+// riceve la stessa risposta di POST /search, con il `seq` del messaggio.
+// Vince l'ultima query: quelle arrivate mentre una ricerca è in corso si
+// sovrascrivono a vicenda e solo la più recente viene eseguita.
+pub async fn get_ws(
+    request: HttpRequest,
+    payload: Payload,
+    dataset_data: Data<RwLock<Dataset>>,
+    config: Data<Config>,
+    pools: Data<ThreadPools>,
+    progress: Data<LoadProgress>,
+) -> Result<HttpResponse, Error> {
+    if !progress.is_loaded() {
+        return Ok(still_loading(&progress));
+    }
+
+    let mut handshake = handshake(request.head())?;
+    let (outgoing_tx, outgoing_rx) = mpsc::unbounded_channel();
+    let (query_tx, query_rx) = watch::channel(None);
+
+    spawn(read_frames(payload, query_tx, outgoing_tx.clone()));
+    spawn(answer_queries(
+        query_rx,
+        outgoing_tx,
+        forced_arm(&request),
+        dataset_data,
+        config,
+        pools,
+    ));
+
+    // i messaggi in uscita finiscono dopo il Close, o quando entrambi i task
+    // hanno chiuso il canale
+    let mut codec = Codec::new();
+    let frames = stream::unfold((outgoing_rx, false), |(mut outgoing, closed)| async move {
+        if closed {
+            return None;
+        }
+        let message = outgoing.recv().await?;
+        let closed = matches!(message, Message::Close(_));
+        Some((message, (outgoing, closed)))
+    })
+    .map(move |message| {
+        let mut buffer = BytesMut::new();
+        codec.encode(message, &mut buffer)?;
+        Ok::<Bytes, ProtocolError>(buffer.freeze())
+    });
+
+    let response = handshake.message_body(BoxBody::new(BodyStream::new(frames)))?;
+    Ok(HttpResponse::from(response))
+}
+
+async fn read_frames(
+    mut payload: Payload,
+    queries: watch::Sender<LatestQuery>,
+    outgoing: mpsc::UnboundedSender<Message>,
+) {
+    let mut codec = Codec::new();
+    let mut buffer = BytesMut::new();
+    let mut seq = 0;
+
+    while let Some(Ok(chunk)) = payload.next().await {
+        buffer.extend_from_slice(&chunk);
+        loop {
+            match codec.decode(&mut buffer) {
+                Ok(Some(Frame::Text(text))) => {
+                    seq += 1;
+                    queries.send_replace(Some((seq, String::from_utf8_lossy(&text).into_owned())));
+                }
+                Ok(Some(Frame::Ping(data))) => {
+                    let _ = outgoing.send(Message::Pong(data));
+                }
+                Ok(Some(Frame::Close(reason))) => {
+                    let _ = outgoing.send(Message::Close(reason));
+                    return;
+                }
+                // binari, pong e frammenti non portano query
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(_) => {
+                    let _ = outgoing.send(Message::Close(Some(CloseCode::Protocol.into())));
+                    return;
+                }
+            }
+        }
+    }
+}
+
+async fn answer_queries(
+    mut queries: watch::Receiver<LatestQuery>,
+    outgoing: mpsc::UnboundedSender<Message>,
+    arm: Option<Arm>,
+    dataset_data: Data<RwLock<Dataset>>,
+    config: Data<Config>,
+    pools: Data<ThreadPools>,
+) {
+    while queries.changed().await.is_ok() {
+        let Some((seq, text)) = queries.borrow_and_update().clone() else {
+            continue;
+        };
+        let dataset_data = dataset_data.clone();
+        let config = config.clone();
+        let pools = pools.clone();
+        let Ok(reply) =
+            spawn_blocking(move || answer(seq, &text, arm, &dataset_data, &config, &pools)).await
+        else {
+            break;
+        };
+        if outgoing.send(Message::Text(reply.into())).is_err() {
+            break;
+        }
+    }
+}
+
+fn answer(
+    seq: u64,
+    text: &str,
+    arm: Option<Arm>,
+    dataset_data: &RwLock<Dataset>,
+    config: &Config,
+    pools: &ThreadPools,
+) -> String {
+    let dataset = dataset_data.read().expect("dataset lock poisoned");
+    let result = serde_json::from_str::<SearchParams>(text)
+        .map_err(|error| format!("invalid search: {error}"))
+        .and_then(|mut params| {
+            if params.stream {
+                return Err("stream is not supported over /ws".to_string());
+            }
+            params.forced_arm = arm;
+            Search::new(params, &dataset, config)
+        })
+        .and_then(|search| {
+            run_search(&search, &dataset, config, pools)
+                .map_err(|size| oversize_message(size, config))
+        });
+
+    match result {
+        Ok(response) => serde_json::to_string(&WsResponse { seq, response }),
+        Err(error) => serde_json::to_string(&WsError { seq, error }),
+    }
+    .expect("search responses are serializable")
+}